@@ -0,0 +1,201 @@
+extern crate getopts;
+extern crate lcov_merge;
+
+mod options;
+
+use std::env;
+use std::error::Error;
+use std::fmt:: { Display };
+use std::fs:: { File };
+use std::io:: { self, BufRead, BufReader, Read, Write };
+use std::path:: { Path };
+use std::process;
+use lcov_merge:: { ReportMerger, PathNormalizer, SourceFilter, RecordWrite };
+use lcov_merge::threshold:: { Thresholds };
+use options:: { Command, Options, OutputFormat };
+
+const PROGRAM: &'static str = "lcov-merge";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let command = match options::parse(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}: {}", PROGRAM, err);
+            eprint!("{}", options::usage(PROGRAM));
+            process::exit(2);
+        }
+    };
+
+    let options = match command {
+        Command::Merge(options) => options,
+        Command::Help => {
+            print!("{}", options::usage(PROGRAM));
+            return;
+        },
+        Command::Version => {
+            println!("{} {}", PROGRAM, env!("CARGO_PKG_VERSION"));
+            return;
+        }
+    };
+
+    if let Err(err) = run(&options) {
+        eprintln!("{}: {}", PROGRAM, err);
+        process::exit(1);
+    }
+}
+
+/// Input that could not be opened, which fails on the first read so that the merger reports
+/// the error, or skips the input in lenient mode.
+struct UnopenedInput(Option<io::Error>);
+
+impl Read for UnopenedInput {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        match self.0.take() {
+            Some(err) => Err(err),
+            None => Ok(0)
+        }
+    }
+}
+
+fn open_input(path: &str) -> Box<dyn BufRead> {
+    if path == "-" {
+        return Box::new(BufReader::new(io::stdin()));
+    }
+    match File::open(path) {
+        Ok(file) => Box::new(BufReader::new(file)),
+        Err(err) => Box::new(BufReader::new(UnopenedInput(Some(err))))
+    }
+}
+
+fn write_output<T: Display>(path: &Option<String>, content: &T) -> io::Result<()> {
+    match *path {
+        Some(ref path) => {
+            let mut output = try!(File::create(path).map_err(|err| {
+                io::Error::new(err.kind(), format!("{}: {}", path, err))
+            }));
+            write!(output, "{}", content)
+        },
+        None => {
+            let stdout = io::stdout();
+            let mut output = stdout.lock();
+            write!(output, "{}", content)
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    let from_stdin = options.inputs.iter().any(|path| path == "-");
+
+    let mut thresholds = Thresholds::new();
+    for &(metric, required) in options.minimums.iter() {
+        thresholds.minimum(metric, required);
+    }
+    for &(ref pattern, metric, required) in options.file_minimums.iter() {
+        try!(thresholds.file_minimum(pattern, metric, required));
+    }
+    for &(ref pattern, metric, required) in options.directory_minimums.iter() {
+        try!(thresholds.directory_minimum(pattern, metric, required));
+    }
+
+    let mut merger = ReportMerger::new();
+    merger
+        .lenient(options.lenient)
+        .conflict_policy(options.conflict_policy)
+        .checksum_mode(options.checksum_mode)
+        .test_naming(options.test_naming.clone());
+
+    if !options.strip_prefixes.is_empty() || !options.substitutions.is_empty() || options.base_dir.is_some() {
+        let mut normalizer = PathNormalizer::new();
+        for &(ref pattern, ref replacement) in options.substitutions.iter() {
+            try!(normalizer.substitute(pattern, replacement));
+        }
+        for prefix in options.strip_prefixes.iter() {
+            normalizer.strip_prefix(prefix.as_str());
+        }
+        if let Some(ref base_dir) = options.base_dir {
+            normalizer.base_dir(base_dir);
+        }
+        merger.path_normalizer(normalizer);
+    }
+    if !options.includes.is_empty() || !options.excludes.is_empty() {
+        let mut filter = SourceFilter::new();
+        for pattern in options.includes.iter() {
+            try!(filter.include(pattern));
+        }
+        for pattern in options.excludes.iter() {
+            try!(filter.exclude(pattern));
+        }
+        merger.source_filter(filter);
+    }
+
+    let report = match options.jobs {
+        Some(jobs) if !from_stdin => try!(merger.merge_parallel(&options.inputs, jobs)),
+        _ => {
+            let inputs = options.inputs.iter().map(|path| (path.clone(), open_input(path)));
+            try!(merger.merge_named_readers(inputs))
+        }
+    };
+    let report = if options.exclusion_markers {
+        report.exclude_marked(options.source_root.as_ref().map(Path::new))
+    } else {
+        report
+    };
+
+    for diagnostic in report.diagnostics().iter() {
+        eprintln!("{}: warning: {}", PROGRAM, diagnostic);
+    }
+    for conflict in report.conflicts().iter() {
+        eprintln!("{}: conflict: {}", PROGRAM, conflict);
+    }
+    if options.summary {
+        eprint!("Summary coverage rate:\n{}", report.coverage());
+    }
+    if options.directory_summary {
+        eprint!("{}", report.directories());
+    }
+
+    match options.format {
+        OutputFormat::Lcov => match options.output {
+            Some(ref path) => try!(report.save_as_with(path, options.function_format)),
+            None => {
+                let stdout = io::stdout();
+                try!(report.records(options.function_format).write_records(&mut stdout.lock()));
+            }
+        },
+        OutputFormat::Cobertura => {
+            let cobertura = match options.source_root {
+                Some(ref source_root) => report.cobertura().source_dir(source_root.as_str()),
+                None => report.cobertura()
+            };
+            try!(write_output(&options.output, &cobertura));
+        }
+        OutputFormat::Json => try!(write_output(&options.output, &report.json())),
+        OutputFormat::Sonar => try!(write_output(&options.output, &report.sonar())),
+        OutputFormat::Codecov => try!(write_output(&options.output, &report.codecov())),
+        OutputFormat::Coveralls => {
+            let coveralls = match options.source_root {
+                Some(ref source_root) => report.coveralls().source_root(source_root),
+                None => report.coveralls()
+            };
+            try!(write_output(&options.output, &coveralls));
+        }
+    }
+    if let Some(ref html_dir) = options.html {
+        let html = match options.source_root {
+            Some(ref source_root) => report.html().source_root(source_root),
+            None => report.html()
+        };
+        try!(html.write_to(html_dir).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", html_dir, err))));
+    }
+
+    let violations = thresholds.check(&report);
+    for violation in violations.iter() {
+        eprintln!("{}: threshold: {}", PROGRAM, violation);
+    }
+    if !violations.is_empty() {
+        return Err(From::from(format!("{} coverage threshold(s) not met", violations.len())));
+    }
+    Ok(())
+}
@@ -0,0 +1,418 @@
+use std::str:: { FromStr };
+use getopts:: { Options as OptionParser, Matches, ParsingStyle };
+use lcov_merge:: { ConflictPolicy, ChecksumMode, TestNaming };
+use lcov_merge::function:: { FunctionFormat };
+use lcov_merge::threshold:: { Metric };
+
+/// Format of the merged output
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OutputFormat {
+    Lcov,
+    Cobertura,
+    Json,
+    Sonar,
+    Codecov,
+    Coveralls
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "lcov" => Ok(OutputFormat::Lcov),
+            "cobertura" => Ok(OutputFormat::Cobertura),
+            "json" => Ok(OutputFormat::Json),
+            "sonar" => Ok(OutputFormat::Sonar),
+            "codecov" => Ok(OutputFormat::Codecov),
+            "coveralls" => Ok(OutputFormat::Coveralls),
+            _ => Err(format!("unknown output format '{}'", value))
+        }
+    }
+}
+
+pub enum Command {
+    Merge(Options),
+    Help,
+    Version
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub inputs: Vec<String>,
+    pub output: Option<String>,
+    pub format: OutputFormat,
+    pub source_root: Option<String>,
+    pub html: Option<String>,
+    pub jobs: Option<usize>,
+    pub lenient: bool,
+    pub conflict_policy: ConflictPolicy,
+    pub checksum_mode: ChecksumMode,
+    pub function_format: FunctionFormat,
+    pub test_naming: TestNaming,
+    pub strip_prefixes: Vec<String>,
+    pub substitutions: Vec<(String, String)>,
+    pub base_dir: Option<String>,
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+    pub exclusion_markers: bool,
+    pub summary: bool,
+    pub directory_summary: bool,
+    pub minimums: Vec<(Metric, f64)>,
+    pub file_minimums: Vec<(String, Metric, f64)>,
+    pub directory_minimums: Vec<(String, Metric, f64)>
+}
+
+fn option_parser() -> OptionParser {
+    let mut parser = OptionParser::new();
+    parser.optmulti("a", "add-tracefile", "add the contents of the tracefile (may be repeated)", "FILE");
+    parser.optopt("o", "output", "write the merged tracefile to FILE instead of stdout", "FILE");
+    parser.optopt("f", "format", "write the merged report as lcov (default), cobertura, json, sonar, codecov or coveralls", "FORMAT");
+    parser.optopt("", "source-root",
+        "write the source file paths relative to DIR in the cobertura and coveralls reports, read relative source files from DIR", "DIR");
+    parser.optopt("", "html", "also write an HTML report with the annotated source files into DIR", "DIR");
+    parser.optopt("j", "jobs", "parse and merge the tracefiles on N threads (0 uses every core)", "N");
+    parser.optflag("", "lenient", "skip malformed records and unreadable tracefiles and report them as warnings");
+    parser.optopt("", "conflict-policy",
+        "resolve checksum mismatches: error (default), keep-first, keep-last, drop-record or drop-line", "POLICY");
+    parser.optopt("", "checksum-mode",
+        "strict (default) fails when only one side of a line has a checksum, mixed merges it by line number", "MODE");
+    parser.optopt("t", "test-name", "collapse every test into a single test named NAME", "NAME");
+    parser.optopt("", "test-names",
+        "keep (default), flatten into one unnamed test, name after the input or prefix with the input", "MODE");
+    parser.optmulti("", "strip-prefix", "remove PREFIX from the source file paths (may be repeated)", "PREFIX");
+    parser.optmulti("", "substitute",
+        "rewrite the source file paths with the regular expression s#PATTERN#REPLACEMENT# (may be repeated)", "REGEXP");
+    parser.optopt("b", "base-directory", "resolve relative source file paths against DIR", "DIR");
+    parser.optmulti("", "include", "merge only the source files matching the glob PATTERN (may be repeated)", "PATTERN");
+    parser.optmulti("", "exclude", "skip the source files matching the glob PATTERN (may be repeated)", "PATTERN");
+    parser.optflag("", "exclusion-markers",
+        "read the source files and drop the lines and branches marked with LCOV_EXCL_* comments");
+    parser.optflag("", "summary", "print the line, function and branch coverage of the merged tracefile to stderr");
+    parser.optflag("", "directory-summary", "print the coverage of every directory to stderr");
+    parser.optopt("", "fail-under-lines", "exit with an error when the line coverage is below PERCENT", "PERCENT");
+    parser.optopt("", "fail-under-functions", "exit with an error when the function coverage is below PERCENT", "PERCENT");
+    parser.optopt("", "fail-under-branches", "exit with an error when the branch coverage is below PERCENT", "PERCENT");
+    parser.optmulti("", "file-threshold",
+        "exit with an error when a source file matching GLOB is below PERCENT of METRIC (may be repeated)", "METRIC:PERCENT:GLOB");
+    parser.optmulti("", "directory-threshold",
+        "exit with an error when a directory matching GLOB is below PERCENT of METRIC (may be repeated)", "METRIC:PERCENT:GLOB");
+    parser.optopt("", "function-format",
+        "write functions as legacy FN/FNDA (default) or as lcov2 FNL/FNA records", "FORMAT");
+    parser.optflag("h", "help", "print this help and exit");
+    parser.optflag("V", "version", "print the version and exit");
+    parser
+}
+
+pub fn usage(program: &str) -> String {
+    let brief = format!("Usage: {} [options] [-a] TRACEFILE...\n\nA TRACEFILE of - is read from stdin.", program);
+    option_parser().usage(&brief)
+}
+
+/// Tracefiles given with `-a` and as free arguments, in the order of the command line.
+///
+/// The arguments are parsed up to each free argument in turn, since the positions of the free arguments are not kept.
+fn tracefiles(args: &[String]) -> Result<Vec<String>, String> {
+    let mut parser = option_parser();
+    parser.parsing_style(ParsingStyle::StopAtFirstFree);
+
+    let mut tracefiles = vec![];
+    let mut rest = args.to_vec();
+    loop {
+        let matches = try!(parser.parse(&rest).map_err(|err| err.to_string()));
+        tracefiles.extend(matches.opt_strs("a"));
+        if matches.free_trailing_start() == Some(0) {
+            tracefiles.extend(matches.free);
+            break;
+        }
+        match matches.free.split_first() {
+            Some((first, others)) => {
+                tracefiles.push(first.clone());
+                rest = others.to_vec();
+            },
+            None => break
+        }
+    }
+    Ok(tracefiles)
+}
+
+fn opt_number(matches: &Matches, name: &str) -> Result<Option<usize>, String> {
+    match matches.opt_str(name) {
+        Some(value) => match value.parse() {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => Err(format!("Option '{}' expects a number, got '{}'", name, value))
+        },
+        None => Ok(None)
+    }
+}
+
+/// Split `s#PATTERN#REPLACEMENT#` of `lcov --substitute`, where any character can be the delimiter.
+fn substitution(value: &str) -> Result<(String, String), String> {
+    let invalid = || format!("Option 'substitute' expects s#PATTERN#REPLACEMENT#, got '{}'", value);
+    let rest = match value.strip_prefix('s') {
+        Some(rest) => rest,
+        None => return Err(invalid())
+    };
+    let delimiter = match rest.chars().next() {
+        Some(delimiter) if !delimiter.is_alphanumeric() => delimiter,
+        _ => return Err(invalid())
+    };
+    let parts: Vec<&str> = rest[delimiter.len_utf8()..].split(delimiter).collect();
+    match parts.as_slice() {
+        [pattern, replacement, ""] => Ok((pattern.to_string(), replacement.to_string())),
+        _ => Err(invalid())
+    }
+}
+
+fn percentage(name: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(percentage) if percentage >= 0.0 && percentage <= 100.0 => Ok(percentage),
+        _ => Err(format!("Option '{}' expects a percentage, got '{}'", name, value))
+    }
+}
+
+/// Split `METRIC:PERCENT:GLOB` of the path thresholds.
+fn path_threshold(name: &str, value: &str) -> Result<(String, Metric, f64), String> {
+    let parts: Vec<&str> = value.splitn(3, ':').collect();
+    if parts.len() != 3 || parts[2].is_empty() {
+        return Err(format!("Option '{}' expects METRIC:PERCENT:GLOB, got '{}'", name, value));
+    }
+    let metric = try!(parts[0].parse());
+    Ok((parts[2].to_string(), metric, try!(percentage(name, parts[1]))))
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let matches = try!(option_parser().parse(args).map_err(|err| err.to_string()));
+
+    if matches.opt_present("h") {
+        return Ok(Command::Help);
+    }
+    if matches.opt_present("V") {
+        return Ok(Command::Version);
+    }
+
+    let inputs = try!(tracefiles(args));
+
+    if inputs.is_empty() {
+        return Err("No tracefile given".to_string());
+    }
+
+    let test_naming = match (matches.opt_str("t"), matches.opt_str("test-names")) {
+        (Some(_), Some(_)) => return Err("Options 'test-name' and 'test-names' are exclusive".to_string()),
+        (Some(name), None) => TestNaming::Flatten(name),
+        (None, Some(mode)) => try!(mode.parse()),
+        (None, None) => TestNaming::Keep
+    };
+
+    let mut substitutions = vec![];
+    for value in matches.opt_strs("substitute").iter() {
+        substitutions.push(try!(substitution(value)));
+    }
+
+    let mut minimums = vec![];
+    for &(name, metric) in [
+        ("fail-under-lines", Metric::Lines),
+        ("fail-under-functions", Metric::Functions),
+        ("fail-under-branches", Metric::Branches)
+    ].iter() {
+        if let Some(value) = matches.opt_str(name) {
+            minimums.push((metric, try!(percentage(name, &value))));
+        }
+    }
+    let mut file_minimums = vec![];
+    for value in matches.opt_strs("file-threshold").iter() {
+        file_minimums.push(try!(path_threshold("file-threshold", value)));
+    }
+    let mut directory_minimums = vec![];
+    for value in matches.opt_strs("directory-threshold").iter() {
+        directory_minimums.push(try!(path_threshold("directory-threshold", value)));
+    }
+
+    let output = match matches.opt_str("o") {
+        Some(ref path) if path == "-" => None,
+        path => path
+    };
+
+    Ok(Command::Merge(Options {
+        inputs: inputs,
+        output: output,
+        format: match matches.opt_str("f") {
+            Some(format) => try!(format.parse()),
+            None => OutputFormat::Lcov
+        },
+        source_root: matches.opt_str("source-root"),
+        html: matches.opt_str("html"),
+        jobs: try!(opt_number(&matches, "j")),
+        lenient: matches.opt_present("lenient"),
+        conflict_policy: match matches.opt_str("conflict-policy") {
+            Some(policy) => try!(policy.parse()),
+            None => ConflictPolicy::Error
+        },
+        checksum_mode: match matches.opt_str("checksum-mode") {
+            Some(mode) => try!(mode.parse()),
+            None => ChecksumMode::Strict
+        },
+        function_format: match matches.opt_str("function-format") {
+            Some(format) => try!(format.parse()),
+            None => FunctionFormat::Legacy
+        },
+        test_naming: test_naming,
+        strip_prefixes: matches.opt_strs("strip-prefix"),
+        substitutions: substitutions,
+        base_dir: matches.opt_str("b"),
+        includes: matches.opt_strs("include"),
+        excludes: matches.opt_strs("exclude"),
+        exclusion_markers: matches.opt_present("exclusion-markers"),
+        summary: matches.opt_present("summary"),
+        directory_summary: matches.opt_present("directory-summary"),
+        minimums: minimums,
+        file_minimums: file_minimums,
+        directory_minimums: directory_minimums
+    }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use lcov_merge:: { ConflictPolicy, ChecksumMode, TestNaming };
+    use lcov_merge::function:: { FunctionFormat };
+    use lcov_merge::threshold:: { Metric };
+    use options:: { parse, Command, Options, OutputFormat };
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn merge_options(values: &[&str]) -> Options {
+        match parse(&args(values)).unwrap() {
+            Command::Merge(options) => options,
+            _ => panic!("expected merge command")
+        }
+    }
+
+    #[test]
+    fn lcov_compatible_arguments() {
+        let options = merge_options(&["-a", "a.info", "-a", "b.info", "-o", "merged.info"]);
+        assert_eq!(options, Options {
+            inputs: vec!["a.info".to_string(), "b.info".to_string()],
+            output: Some("merged.info".to_string()),
+            format: OutputFormat::Lcov,
+            source_root: None,
+            html: None,
+            jobs: None,
+            lenient: false,
+            conflict_policy: ConflictPolicy::Error,
+            checksum_mode: ChecksumMode::Strict,
+            function_format: FunctionFormat::Legacy,
+            test_naming: TestNaming::Keep,
+            strip_prefixes: vec![],
+            substitutions: vec![],
+            base_dir: None,
+            includes: vec![],
+            excludes: vec![],
+            exclusion_markers: false,
+            summary: false,
+            directory_summary: false,
+            minimums: vec![],
+            file_minimums: vec![],
+            directory_minimums: vec![]
+        });
+    }
+
+    #[test]
+    fn free_arguments_and_stdout() {
+        let options = merge_options(&["a.info", "--add-tracefile", "b.info", "-o", "-", "c.info", "-a", "d.info", "--", "-e.info"]);
+        assert_eq!(options.inputs, vec!["a.info", "b.info", "c.info", "d.info", "-e.info"]);
+        assert_eq!(options.output, None);
+    }
+
+    #[test]
+    fn html() {
+        let options = merge_options(&["--html", "coverage", "--source-root", "/src", "a.info"]);
+        assert_eq!(options.html, Some("coverage".to_string()));
+        assert_eq!(options.source_root, Some("/src".to_string()));
+    }
+
+    #[test]
+    fn jobs() {
+        assert_eq!(merge_options(&["-j", "4", "a.info"]).jobs, Some(4));
+        assert!(parse(&args(&["--jobs", "many", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn conflict_policy() {
+        let options = merge_options(&["--conflict-policy", "keep-last", "a.info"]);
+        assert_eq!(options.conflict_policy, ConflictPolicy::KeepLast);
+        assert!(parse(&args(&["--conflict-policy", "newest", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn checksum_mode() {
+        assert_eq!(merge_options(&["--checksum-mode", "mixed", "a.info"]).checksum_mode, ChecksumMode::Mixed);
+        assert!(parse(&args(&["--checksum-mode", "loose", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn function_format() {
+        assert_eq!(merge_options(&["--function-format", "lcov2", "a.info"]).function_format, FunctionFormat::Lcov2);
+        assert!(parse(&args(&["--function-format", "fnl", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn test_naming() {
+        assert_eq!(merge_options(&["-t", "all", "a.info"]).test_naming, TestNaming::Flatten("all".to_string()));
+        assert_eq!(merge_options(&["--test-names", "prefix", "a.info"]).test_naming, TestNaming::PrefixInput);
+        assert!(parse(&args(&["-t", "all", "--test-names", "input", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn path_normalization() {
+        let options = merge_options(&[
+            "--strip-prefix", "/builds/xyz/", "--substitute", "s#^/home/[^/]+/#/src/#", "-b", "/src", "a.info"
+        ]);
+        assert_eq!(options.strip_prefixes, vec!["/builds/xyz/".to_string()]);
+        assert_eq!(options.substitutions, vec![("^/home/[^/]+/".to_string(), "/src/".to_string())]);
+        assert_eq!(options.base_dir, Some("/src".to_string()));
+        assert!(parse(&args(&["--substitute", "s#/home#/src", "a.info"])).is_err());
+        assert!(parse(&args(&["--substitute", "s#/home#/src#g", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn source_filter() {
+        let options = merge_options(&["--include", "/src/*", "--exclude", "/usr/include/*", "--exclude", "*/generated/*", "a.info"]);
+        assert_eq!(options.includes, vec!["/src/*".to_string()]);
+        assert_eq!(options.excludes, vec!["/usr/include/*".to_string(), "*/generated/*".to_string()]);
+        assert!(merge_options(&["--exclusion-markers", "a.info"]).exclusion_markers);
+    }
+
+    #[test]
+    fn thresholds() {
+        let options = merge_options(&[
+            "--fail-under-lines", "80", "--fail-under-branches", "62.5",
+            "--file-threshold", "lines:90:src/report/*", "--directory-threshold", "branches:50:*/generated", "a.info"
+        ]);
+        assert_eq!(options.minimums, vec![(Metric::Lines, 80.0), (Metric::Branches, 62.5)]);
+        assert_eq!(options.file_minimums, vec![("src/report/*".to_string(), Metric::Lines, 90.0)]);
+        assert_eq!(options.directory_minimums, vec![("*/generated".to_string(), Metric::Branches, 50.0)]);
+        assert!(parse(&args(&["--fail-under-lines", "120", "a.info"])).is_err());
+        assert!(parse(&args(&["--file-threshold", "lines:90", "a.info"])).is_err());
+        assert!(parse(&args(&["--file-threshold", "statements:90:src/*", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn output_format() {
+        let options = merge_options(&["-f", "cobertura", "--source-root", "/src", "a.info"]);
+        assert_eq!(options.format, OutputFormat::Cobertura);
+        assert_eq!(options.source_root, Some("/src".to_string()));
+        assert_eq!(merge_options(&["--format", "json", "a.info"]).format, OutputFormat::Json);
+        assert_eq!(merge_options(&["-f", "sonar", "a.info"]).format, OutputFormat::Sonar);
+        assert_eq!(merge_options(&["-f", "codecov", "a.info"]).format, OutputFormat::Codecov);
+        assert_eq!(merge_options(&["-f", "coveralls", "a.info"]).format, OutputFormat::Coveralls);
+        assert!(parse(&args(&["--format", "clover", "a.info"])).is_err());
+    }
+
+    #[test]
+    fn missing_tracefile() {
+        assert!(parse(&args(&["-o", "merged.info"])).is_err());
+    }
+}
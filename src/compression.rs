@@ -0,0 +1,142 @@
+use std::io:: { BufRead, BufReader, Write, Result as IOResult };
+use std::path::Path;
+use flate2::Compression as GzipLevel;
+use flate2::bufread:: { MultiGzDecoder };
+use flate2::write:: { GzEncoder };
+use zstd::stream::read:: { Decoder as ZstdDecoder };
+use zstd::stream::write:: { Encoder as ZstdEncoder };
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression format of a tracefile
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd
+}
+
+impl Compression {
+    /// Detect the compression from the magic bytes, without consuming them.
+    pub fn detect<R: BufRead>(reader: &mut R) -> IOResult<Compression> {
+        let head = try!(reader.fill_buf());
+        if head.starts_with(&GZIP_MAGIC) {
+            return Ok(Compression::Gzip);
+        }
+        if head.starts_with(&ZSTD_MAGIC) {
+            return Ok(Compression::Zstd);
+        }
+        Ok(Compression::None)
+    }
+
+    /// Select the compression from the extension of the output path (`.gz` or `.zst`).
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Compression {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None
+        }
+    }
+}
+
+/// Wrap the reader with a decoder when the input is compressed.
+pub fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> IOResult<Box<dyn BufRead + 'a>> {
+    let decoded: Box<dyn BufRead + 'a> = match try!(Compression::detect(&mut reader)) {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(try!(ZstdDecoder::with_buffer(reader))))
+    };
+    Ok(decoded)
+}
+
+/// Writer that compresses the written records.
+///
+/// `finish` must be called to flush the trailer of the compressed stream.
+pub enum Encoder<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    Zstd(ZstdEncoder<'static, W>)
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(writer: W, compression: Compression) -> IOResult<Self> {
+        let encoder = match compression {
+            Compression::None => Encoder::Plain(writer),
+            Compression::Gzip => Encoder::Gzip(GzEncoder::new(writer, GzipLevel::default())),
+            Compression::Zstd => Encoder::Zstd(try!(ZstdEncoder::new(writer, 0)))
+        };
+        Ok(encoder)
+    }
+    pub fn finish(self) -> IOResult<W> {
+        match self {
+            Encoder::Plain(mut writer) => {
+                try!(writer.flush());
+                Ok(writer)
+            },
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Zstd(encoder) => encoder.finish()
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        match *self {
+            Encoder::Plain(ref mut writer) => writer.write(buf),
+            Encoder::Gzip(ref mut encoder) => encoder.write(buf),
+            Encoder::Zstd(ref mut encoder) => encoder.write(buf)
+        }
+    }
+    fn flush(&mut self) -> IOResult<()> {
+        match *self {
+            Encoder::Plain(ref mut writer) => writer.flush(),
+            Encoder::Gzip(ref mut encoder) => encoder.flush(),
+            Encoder::Zstd(ref mut encoder) => encoder.flush()
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io:: { Cursor, Read, Write };
+    use compression:: { Compression, Encoder, decompress };
+
+    fn roundtrip(compression: Compression) {
+        let content = "TN:\nSF:/path/to/source.c\nDA:1,1\nend_of_record\n";
+        let compressed = {
+            let mut encoder = Encoder::new(Vec::new(), compression).unwrap();
+            encoder.write_all(content.as_bytes()).unwrap();
+            encoder.finish().unwrap()
+        };
+        let mut reader = Cursor::new(compressed);
+        assert_eq!(Compression::detect(&mut reader).unwrap(), compression);
+
+        let mut decoded = String::new();
+        decompress(reader).unwrap().read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, content);
+    }
+
+    #[test]
+    fn plain() {
+        roundtrip(Compression::None);
+    }
+
+    #[test]
+    fn gzip() {
+        roundtrip(Compression::Gzip);
+    }
+
+    #[test]
+    fn zstd() {
+        roundtrip(Compression::Zstd);
+    }
+
+    #[test]
+    fn from_path() {
+        assert_eq!(Compression::from_path("merged.info.gz"), Compression::Gzip);
+        assert_eq!(Compression::from_path("merged.info.zst"), Compression::Zstd);
+        assert_eq!(Compression::from_path("merged.info"), Compression::None);
+    }
+}
@@ -1,10 +1,25 @@
 extern crate lcov_parser;
+extern crate flate2;
+extern crate zstd;
+extern crate regex;
+extern crate glob;
+extern crate serde;
+extern crate serde_json;
+extern crate md5;
+extern crate xml;
 
 #[macro_use]
 mod macros;
 mod merger;
 mod record;
+mod compression;
 mod report;
 
-pub use self::merger:: { merge_files };
+pub use self::merger:: { merge_files, merge_readers, ReportMerger };
+pub use self::record:: { RecordWrite };
+pub use self::merger::ops:: { MergeError };
+pub use self::merger::conflict:: { ConflictPolicy, ChecksumMode, Conflict };
+pub use self::merger::naming:: { TestNaming };
+pub use self::merger::path:: { PathNormalizer };
+pub use self::merger::filter:: { SourceFilter };
 pub use self::report::*;
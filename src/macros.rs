@@ -18,6 +18,26 @@ macro_rules! impl_summary {
     }
 }
 
+#[macro_export]
+macro_rules! impl_summary_mut {
+    ($dest:ty, $field:ident<$key:ty, $value:ty>) => {
+        impl $crate::report::summary::SummaryMut<$key, $value> for $dest {
+            fn iter_mut(&mut self) -> ::std::collections::btree_map::IterMut<$key, $value> {
+                self.$field.iter_mut()
+            }
+            fn get_mut(&mut self, key: &$key) -> Option<&mut $value> {
+                self.$field.get_mut(key)
+            }
+            fn remove(&mut self, key: &$key) -> Option<$value> {
+                self.$field.remove(key)
+            }
+            fn retain<F: FnMut(&$key, &mut $value) -> bool>(&mut self, f: F) {
+                self.$field.retain(f)
+            }
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! impl_try_merge_self_summary {
     ($dest:ty:$field:ident, $err:ty) => {
@@ -0,0 +1,315 @@
+use std::collections::btree_set:: { BTreeSet };
+use std::str:: { FromStr };
+use lcov_parser:: { LineData };
+use report::file:: { File, Files };
+use report::line:: { Line, Lines };
+use report::summary:: { Summary, SummaryMut };
+use report::attribute:: { SourceFile, TestName, LineNumber };
+use merger::ops:: { TryMerge, MergeResult, MergeLine, TestError, ChecksumError };
+
+pub use report::conflict:: { ConflictPolicy, Conflict };
+
+/// How to merge a line when only one of the tracefiles carries its checksum
+///
+/// Lines without a checksum on both sides are always merged by line number.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ChecksumMode {
+    /// Fail the merge with `ChecksumError::Empty`
+    #[default]
+    Strict,
+    /// Merge by line number and keep the checksum of the side that has one
+    Mixed
+}
+
+impl FromStr for ChecksumMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "strict" => Ok(ChecksumMode::Strict),
+            "mixed" => Ok(ChecksumMode::Mixed),
+            _ => Err(format!("unknown checksum mode '{}'", value))
+        }
+    }
+}
+
+/// Applies the `ConflictPolicy` to the lines of a record and before the files are merged, and records every conflict.
+#[derive(Debug, Clone, Default)]
+pub struct ConflictResolver {
+    policy: ConflictPolicy,
+    checksum_mode: ChecksumMode,
+    conflicts: Vec<Conflict>,
+    dropped: BTreeSet<(SourceFile, TestName, LineNumber)>
+}
+
+impl ConflictResolver {
+    pub fn new(policy: ConflictPolicy, checksum_mode: ChecksumMode) -> Self {
+        ConflictResolver {
+            policy: policy,
+            checksum_mode: checksum_mode,
+            conflicts: vec![],
+            dropped: BTreeSet::new()
+        }
+    }
+    pub fn policy(&self) -> ConflictPolicy {
+        self.policy
+    }
+    pub fn checksum_mode(&self) -> ChecksumMode {
+        self.checksum_mode
+    }
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// Merge the file into the files after resolving the conflicts.
+    pub fn merge(&mut self, files: &mut Files, source_file: &SourceFile, mut file: File) -> MergeResult<TestError> {
+        self.remove_dropped(source_file, &mut file);
+
+        if let Some(merged) = files.get_mut(source_file) {
+            try!(self.resolve(source_file, merged, &mut file));
+        }
+        files.try_merge((source_file, &file))
+    }
+
+    /// Merge the files resolved by another resolver, which are the later ones.
+    pub fn merge_files(&mut self, files: &mut Files, other: (Files, ConflictResolver)) -> MergeResult<TestError> {
+        let (other_files, other_resolver) = other;
+
+        self.conflicts.extend(other_resolver.conflicts);
+        if !other_resolver.dropped.is_empty() {
+            self.dropped.extend(other_resolver.dropped);
+            for (source_file, file) in files.iter_mut() {
+                self.remove_dropped(source_file, file);
+            }
+        }
+        for (source_file, file) in other_files.iter() {
+            try!(self.merge(files, source_file, file.clone()));
+        }
+        Ok(())
+    }
+
+    /// Merge a line into the lines of the record being read, resolving a conflict with a line of the same record.
+    ///
+    /// Returns `false` when the policy drops the record.
+    pub fn merge_line(&mut self, source_file: &SourceFile, test_name: &TestName, lines: &mut Lines, line_data: &LineData) -> Result<bool, ChecksumError> {
+        let mut line = Line::from(line_data);
+        let merged = match lines.get_mut(&line_data.line) {
+            Some(merged) => merged,
+            None => return lines.try_merge(line_data).map(|_| true)
+        };
+        if self.checksum_mode == ChecksumMode::Mixed {
+            share_checksum(merged, &mut line);
+        }
+        if !is_conflict(merged, &line) {
+            try!(merged.try_merge(&line));
+            return Ok(true);
+        }
+        if self.policy == ConflictPolicy::Error {
+            return Err(ChecksumError::Mismatch(MergeLine::from(&*merged), MergeLine::from(&line)));
+        }
+        self.record(source_file, test_name, merged, &line);
+        match self.policy {
+            ConflictPolicy::KeepLast => *merged = line,
+            ConflictPolicy::DropLine => {
+                lines.remove(&line_data.line);
+                self.dropped.insert((source_file.clone(), test_name.clone(), line_data.line));
+            },
+            ConflictPolicy::DropRecord => return Ok(false),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn record(&mut self, source_file: &SourceFile, test_name: &TestName, first: &Line, last: &Line) {
+        self.conflicts.push(Conflict {
+            source_file: source_file.clone(),
+            test_name: test_name.clone(),
+            line: *first.line_number(),
+            first: first.checksum().cloned(),
+            last: last.checksum().cloned(),
+            policy: self.policy
+        });
+    }
+
+    fn remove_dropped(&self, source_file: &SourceFile, file: &mut File) {
+        for (test_name, test) in file.tests_mut().iter_mut() {
+            test.lines_mut().retain(|line_number, _| {
+                let key = (source_file.clone(), test_name.clone(), *line_number);
+                !self.dropped.contains(&key)
+            });
+        }
+    }
+
+    fn resolve(&mut self, source_file: &SourceFile, merged: &mut File, file: &mut File) -> MergeResult<ChecksumError> {
+        let mut dropped_tests = vec![];
+
+        for (test_name, test) in file.tests_mut().iter_mut() {
+            let merged_test = match merged.tests_mut().get_mut(test_name) {
+                Some(merged_test) => merged_test,
+                None => continue
+            };
+            if self.checksum_mode == ChecksumMode::Mixed {
+                share_checksums(merged_test.lines_mut(), test.lines_mut());
+            }
+            let conflicts: Vec<(Line, Line)> = test.lines().iter()
+                .filter_map(|(line_number, line)| {
+                    merged_test.lines().get(line_number)
+                        .filter(|merged_line| is_conflict(merged_line, line))
+                        .map(|merged_line| (merged_line.clone(), line.clone()))
+                })
+                .collect();
+
+            for (first, last) in conflicts.iter() {
+                let line_number = *first.line_number();
+                match self.policy {
+                    ConflictPolicy::Error => {
+                        return Err(ChecksumError::Mismatch(MergeLine::from(first), MergeLine::from(last)));
+                    },
+                    ConflictPolicy::KeepFirst => {
+                        test.lines_mut().remove(&line_number);
+                    },
+                    ConflictPolicy::KeepLast => {
+                        merged_test.lines_mut().remove(&line_number);
+                    },
+                    ConflictPolicy::DropLine => {
+                        test.lines_mut().remove(&line_number);
+                        merged_test.lines_mut().remove(&line_number);
+                        self.dropped.insert((source_file.clone(), test_name.clone(), line_number));
+                    },
+                    ConflictPolicy::DropRecord => {}
+                }
+                self.record(source_file, test_name, first, last);
+            }
+            if self.policy == ConflictPolicy::DropRecord && !conflicts.is_empty() {
+                dropped_tests.push(test_name.clone());
+            }
+        }
+
+        for test_name in dropped_tests.iter() {
+            file.tests_mut().remove(test_name);
+        }
+        Ok(())
+    }
+}
+
+fn share_checksums(merged: &mut Lines, lines: &mut Lines) {
+    for (line_number, line) in lines.iter_mut() {
+        if let Some(merged_line) = merged.get_mut(line_number) {
+            share_checksum(merged_line, line);
+        }
+    }
+}
+
+fn share_checksum(merged: &mut Line, line: &mut Line) {
+    match (merged.has_checkshum(), line.has_checkshum()) {
+        (true, false) => line.set_checksum(merged.checksum().cloned()),
+        (false, true) => merged.set_checksum(line.checksum().cloned()),
+        _ => {}
+    }
+}
+
+fn is_conflict(merged: &Line, line: &Line) -> bool {
+    match (merged.checksum(), line.checksum()) {
+        (Some(merged_checksum), Some(checksum)) => merged_checksum != checksum,
+        _ => false
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use lcov_parser:: { LineData };
+    use merger::ops::*;
+    use merger::conflict:: { ConflictPolicy, ChecksumMode, ConflictResolver };
+    use report::file:: { File, Files };
+    use report::test:: { Tests };
+    use report::line:: { Line };
+    use report::summary:: { Summary };
+
+    fn file(lines: &[(u32, u32, &str)]) -> File {
+        let mut tests = Tests::new();
+        let test_name = "test".to_string();
+        for &(line, count, checksum) in lines.iter() {
+            let checksum = if checksum.is_empty() { None } else { Some(checksum.to_string()) };
+            let data = LineData { line: line, count: count, checksum: checksum };
+            tests.try_merge((&test_name, &data)).unwrap();
+        }
+        File::new(tests)
+    }
+
+    fn merge(policy: ConflictPolicy) -> Result<(Files, ConflictResolver), TestError> {
+        let source_file = "/path/to/source.c".to_string();
+        let mut files = Files::new();
+        let mut resolver = ConflictResolver::new(policy, ChecksumMode::Strict);
+
+        try!(resolver.merge(&mut files, &source_file, file(&[(1, 1, "a"), (2, 1, "b")])));
+        try!(resolver.merge(&mut files, &source_file, file(&[(1, 2, "a"), (2, 2, "c")])));
+        Ok((files, resolver))
+    }
+
+    fn line(files: &Files, line_number: u32) -> Option<(u32, String)> {
+        let file = files.get(&"/path/to/source.c".to_string()).unwrap();
+        let test = file.get_test(&"test".to_string()).unwrap();
+        test.lines().get(&line_number).map(|line: &Line| {
+            (*line.execution_count(), line.checksum().unwrap().clone())
+        })
+    }
+
+    #[test]
+    fn error() {
+        assert!(merge(ConflictPolicy::Error).is_err());
+    }
+
+    #[test]
+    fn keep_first() {
+        let (files, resolver) = merge(ConflictPolicy::KeepFirst).unwrap();
+        assert_eq!(line(&files, 1), Some((3, "a".to_string())));
+        assert_eq!(line(&files, 2), Some((1, "b".to_string())));
+        assert_eq!(resolver.conflicts().len(), 1);
+        assert_eq!(resolver.conflicts()[0].line, 2);
+    }
+
+    #[test]
+    fn keep_last() {
+        let (files, _) = merge(ConflictPolicy::KeepLast).unwrap();
+        assert_eq!(line(&files, 1), Some((3, "a".to_string())));
+        assert_eq!(line(&files, 2), Some((2, "c".to_string())));
+    }
+
+    #[test]
+    fn drop_record() {
+        let (files, resolver) = merge(ConflictPolicy::DropRecord).unwrap();
+        assert_eq!(line(&files, 1), Some((1, "a".to_string())));
+        assert_eq!(line(&files, 2), Some((1, "b".to_string())));
+        assert_eq!(resolver.conflicts().len(), 1);
+    }
+
+    #[test]
+    fn drop_line() {
+        let (mut files, mut resolver) = merge(ConflictPolicy::DropLine).unwrap();
+        assert_eq!(line(&files, 1), Some((3, "a".to_string())));
+        assert_eq!(line(&files, 2), None);
+
+        let source_file = "/path/to/source.c".to_string();
+        resolver.merge(&mut files, &source_file, file(&[(2, 1, "b")])).unwrap();
+        assert_eq!(line(&files, 2), None);
+    }
+
+    #[test]
+    fn mixed_checksums() {
+        let source_file = "/path/to/source.c".to_string();
+        let mut files = Files::new();
+
+        let mut resolver = ConflictResolver::new(ConflictPolicy::Error, ChecksumMode::Strict);
+        resolver.merge(&mut files, &source_file, file(&[(1, 1, "a"), (2, 1, "")])).unwrap();
+        assert!(resolver.merge(&mut files, &source_file, file(&[(1, 1, ""), (2, 1, "b")])).is_err());
+
+        let mut files = Files::new();
+        let mut resolver = ConflictResolver::new(ConflictPolicy::Error, ChecksumMode::Mixed);
+        resolver.merge(&mut files, &source_file, file(&[(1, 1, "a"), (2, 1, "")])).unwrap();
+        resolver.merge(&mut files, &source_file, file(&[(1, 1, ""), (2, 1, "b")])).unwrap();
+
+        assert_eq!(line(&files, 1), Some((2, "a".to_string())));
+        assert_eq!(line(&files, 2), Some((2, "b".to_string())));
+    }
+}
@@ -0,0 +1,109 @@
+use std::env;
+use glob:: { Pattern, PatternError };
+
+/// Selects the source files by glob patterns, like `lcov --extract` and `lcov --remove`
+///
+/// A source file is kept when it matches one of the include patterns (or no include pattern
+/// is given) and matches none of the exclude patterns. As in lcov, `*` also matches `/`.
+/// A leading `~` of a pattern is expanded to the `HOME` directory.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::SourceFilter;
+///
+/// let mut filter = SourceFilter::new();
+/// filter
+///     .include("/src/*").unwrap()
+///     .exclude("*/generated/*").unwrap();
+///
+/// assert!(filter.matches("/src/lib/main.c"));
+/// assert!(!filter.matches("/src/generated/parser.c"));
+/// assert!(!filter.matches("/usr/include/stdio.h"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SourceFilter {
+    includes: Vec<Pattern>,
+    excludes: Vec<Pattern>
+}
+
+impl SourceFilter {
+    pub fn new() -> Self {
+        SourceFilter {
+            includes: vec![],
+            excludes: vec![]
+        }
+    }
+    /// Keep only the source files that match one of the include patterns.
+    pub fn include(&mut self, pattern: &str) -> Result<&mut Self, PatternError> {
+        self.includes.push(try!(Pattern::new(&expand_home(pattern))));
+        Ok(self)
+    }
+    /// Drop the source files that match the pattern.
+    pub fn exclude(&mut self, pattern: &str) -> Result<&mut Self, PatternError> {
+        self.excludes.push(try!(Pattern::new(&expand_home(pattern))));
+        Ok(self)
+    }
+    pub fn matches(&self, source_file: &str) -> bool {
+        let included = self.includes.is_empty() ||
+            self.includes.iter().any(|pattern| pattern.matches(source_file));
+        included && !self.excludes.iter().any(|pattern| pattern.matches(source_file))
+    }
+}
+
+/// Pattern with a leading `~` or `~/` replaced by the `HOME` directory, kept as it is without `HOME`
+fn expand_home(pattern: &str) -> String {
+    let rest = match pattern.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => return pattern.to_string()
+    };
+    match env::var("HOME") {
+        Ok(home) => format!("{}{}", home.trim_end_matches('/'), rest),
+        Err(_) => pattern.to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use merger::filter:: { SourceFilter };
+
+    #[test]
+    fn matches_without_patterns() {
+        assert!(SourceFilter::new().matches("/usr/include/stdio.h"));
+    }
+
+    #[test]
+    fn exclude() {
+        let mut filter = SourceFilter::new();
+        filter
+            .exclude("/usr/include/*").unwrap()
+            .exclude("*/.cargo/registry/*").unwrap();
+        assert!(!filter.matches("/usr/include/sys/types.h"));
+        assert!(!filter.matches("/home/ci/.cargo/registry/src/lib.rs"));
+        assert!(filter.matches("/src/main.c"));
+        assert!(SourceFilter::new().exclude("[").is_err());
+    }
+
+    #[test]
+    fn exclude_home() {
+        let home = match env::var("HOME") {
+            Ok(home) => home,
+            Err(_) => return
+        };
+        let mut filter = SourceFilter::new();
+        filter.exclude("~/.cargo/registry/*").unwrap();
+        assert!(!filter.matches(&format!("{}/.cargo/registry/src/serde/lib.rs", home.trim_end_matches('/'))));
+        assert!(filter.matches("/src/main.rs"));
+        assert!(SourceFilter::new().exclude("~user/*").unwrap().matches("/home/user/main.rs"));
+    }
+
+    #[test]
+    fn include() {
+        let mut filter = SourceFilter::new();
+        filter.include("src/**").unwrap();
+        assert!(filter.matches("src/report/mod.rs"));
+        assert!(!filter.matches("tests/fixtures/fixture.c"));
+    }
+}
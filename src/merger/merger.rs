@@ -1,78 +1,529 @@
 use std::path::Path;
+use std::str;
+use std::fs:: { File as InputFile };
+use std::io:: { BufRead, BufReader, Error as IOError, ErrorKind };
 use std::convert::{ AsRef };
 use std::result:: { Result };
+use std::collections::btree_map:: { BTreeMap };
 use lcov_parser:: {
-    LCOVParser, LCOVRecord, LineData, FunctionData as FunctionDataRecord,
-    BranchData as BranchDataRecord,
-    FunctionName, FromFile
+    LCOVRecord, LineData, FunctionData as FunctionDataRecord,
+    FunctionName, RecordParseError
 };
+use compression:: { decompress };
 use report:: { Report };
 use report::test:: { Tests };
+use report::summary:: { SummaryMut };
 use report::file:: { File, Files };
-use merger::ops:: { Merge, TryMerge, MergeError, TestError, ChecksumError, BranchError, FunctionError, MergeResult };
+use report::branch:: { Branch };
+use report::function:: { Function };
+use report::attribute:: { ExecutionCount, LineNumber };
+use report::diagnostic:: { Diagnostic };
+use report::json:: { JsonReport };
+use report::cobertura:: { CoberturaReport };
+use report::gcov:: { GcovReport };
+use report::llvm:: { LlvmReport, LLVM_EXPORT_TYPE };
+use serde_json:: { Value };
+use merger::parallel;
+use merger::record:: { Record, read_record };
+use merger::conflict:: { ConflictPolicy, ChecksumMode, ConflictResolver };
+use merger::naming:: { TestNaming };
+use merger::path:: { PathNormalizer };
+use merger::filter:: { SourceFilter };
+use merger::ops:: {
+    Merge, TryMerge, MergeError, TestError, ChecksumError, BranchError, FunctionError, MergeResult,
+    parse_error_details
+};
 
 pub struct ReportMerger {
+    lenient: bool,
+    resolver: ConflictResolver,
+    test_naming: TestNaming,
+    path_normalizer: Option<PathNormalizer>,
+    source_filter: Option<SourceFilter>,
+    input: String,
+    line: u32,
     test_name: Option<String>,
     source_name: Option<String>,
+    excluded: bool,
+    function_locations: BTreeMap<u32, (LineNumber, Option<LineNumber>)>,
     tests: Tests,
-    files: Files
+    files: Files,
+    diagnostics: Vec<Diagnostic>
 }
 
 impl ReportMerger {
     pub fn new() -> Self {
         ReportMerger {
+            lenient: false,
+            resolver: ConflictResolver::new(ConflictPolicy::Error, ChecksumMode::Strict),
+            test_naming: TestNaming::Keep,
+            path_normalizer: None,
+            source_filter: None,
+            input: String::new(),
+            line: 0,
             test_name: None,
             source_name: None,
+            excluded: false,
+            function_locations: BTreeMap::new(),
             tests: Tests::new(),
-            files: Files::new()
+            files: Files::new(),
+            diagnostics: vec![]
         }
     }
+    /// Skip malformed records, the blocks that fail to merge or to be read, and the inputs
+    /// that can't be opened, instead of failing.
+    ///
+    /// Every skipped record, block or input is reported by `Report::diagnostics`.
+    pub fn lenient(&mut self, lenient: bool) -> &mut Self {
+        self.lenient = lenient;
+        self
+    }
+    /// Resolve checksum mismatches by the policy instead of failing.
+    ///
+    /// Every resolved conflict is reported by `Report::conflicts`.
+    pub fn conflict_policy(&mut self, policy: ConflictPolicy) -> &mut Self {
+        self.resolver = ConflictResolver::new(policy, self.resolver.checksum_mode());
+        self
+    }
+    /// Merge lines when only one of the tracefiles carries a checksum.
+    ///
+    /// Lines without checksums on both sides are always merged by line number.
+    pub fn checksum_mode(&mut self, checksum_mode: ChecksumMode) -> &mut Self {
+        self.resolver = ConflictResolver::new(self.resolver.policy(), checksum_mode);
+        self
+    }
+    /// Rename the tests of the tracefiles, e.g. to collapse them into a single test.
+    pub fn test_naming(&mut self, test_naming: TestNaming) -> &mut Self {
+        self.test_naming = test_naming;
+        self
+    }
+    /// Normalize the paths of the source files, which are used as the keys of the merged files.
+    pub fn path_normalizer(&mut self, path_normalizer: PathNormalizer) -> &mut Self {
+        self.path_normalizer = Some(path_normalizer);
+        self
+    }
+    /// Merge only the source files selected by the filter.
+    ///
+    /// The filter is matched against the normalized paths, and the records of the
+    /// source files filtered out are skipped without being parsed.
+    pub fn source_filter(&mut self, source_filter: SourceFilter) -> &mut Self {
+        self.source_filter = Some(source_filter);
+        self
+    }
     pub fn merge<T: AsRef<Path>>(&mut self, files: &[T]) -> Result<Report, MergeError> {
         for file in files.iter() {
             try!(self.process_file(file));
         }
-        Ok(Report::new(self.files.clone()))
+        Ok(self.report())
+    }
+    pub fn merge_reader<R: BufRead>(&mut self, reader: R) -> Result<Report, MergeError> {
+        try!(self.process_reader("-", reader));
+        Ok(self.report())
+    }
+    pub fn merge_readers<I, R>(&mut self, readers: I) -> Result<Report, MergeError>
+        where I: IntoIterator<Item=R>, R: BufRead {
+        for (index, reader) in readers.into_iter().enumerate() {
+            try!(self.process_reader(&format!("<reader {}>", index + 1), reader));
+        }
+        Ok(self.report())
+    }
+    /// Merge the readers, using the name of each reader in diagnostics.
+    pub fn merge_named_readers<I, R>(&mut self, readers: I) -> Result<Report, MergeError>
+        where I: IntoIterator<Item=(String, R)>, R: BufRead {
+        for (name, reader) in readers {
+            try!(self.process_reader(&name, reader));
+        }
+        Ok(self.report())
+    }
+    /// Merge the tracefiles on up to `jobs` threads (0 uses every available core).
+    ///
+    /// Each tracefile is parsed on its own, and the results are combined pairwise,
+    /// or from left to right when the conflict policy depends on the order of the inputs.
+    /// The merged report is the same as the one produced by `merge`.
+    pub fn merge_parallel<T: AsRef<Path> + Sync>(&mut self, files: &[T], jobs: usize) -> Result<Report, MergeError> {
+        let jobs = if jobs == 0 { parallel::default_jobs() } else { jobs };
+        let template = self.fork();
+        let parsed = parallel::map(files.iter().collect(), jobs, |file| -> Result<_, MergeError> {
+            let mut merger = template.fork();
+            try!(merger.process_file(file));
+            Ok((merger.files, merger.resolver, merger.diagnostics))
+        });
+
+        let mut parsed_files = Vec::with_capacity(parsed.len());
+        for result in parsed.into_iter() {
+            let (files, resolver, diagnostics) = try!(result);
+            parsed_files.push((files, resolver));
+            self.diagnostics.extend(diagnostics);
+        }
+        if self.resolver.policy().is_order_dependent() {
+            for parsed in parsed_files.into_iter() {
+                try!(self.resolver.merge_files(&mut self.files, parsed));
+            }
+            return Ok(self.report());
+        }
+        let merged = try!(parallel::reduce(parsed_files, jobs, |left, right| {
+            let (mut files, mut resolver) = left;
+            try!(resolver.merge_files(&mut files, right));
+            Ok((files, resolver))
+        }).map_err(|err: TestError| MergeError::from(err)));
+
+        if let Some(merged) = merged {
+            try!(self.resolver.merge_files(&mut self.files, merged));
+        }
+        Ok(self.report())
+    }
+    fn fork(&self) -> Self {
+        let mut merger = ReportMerger::new();
+        merger.lenient = self.lenient;
+        merger.resolver = ConflictResolver::new(self.resolver.policy(), self.resolver.checksum_mode());
+        merger.test_naming = self.test_naming.clone();
+        merger.path_normalizer = self.path_normalizer.clone();
+        merger.source_filter = self.source_filter.clone();
+        merger
+    }
+    fn report(&self) -> Report {
+        Report::new(self.files.clone())
+            .with_diagnostics(self.diagnostics.clone())
+            .with_conflicts(self.resolver.conflicts().to_vec())
     }
     fn process_file<T: AsRef<Path>>(&mut self, file: T) -> Result<(), MergeError> {
-        let mut parser = try!(LCOVParser::from_file(file));
+        let name = file.as_ref().display().to_string();
+        match InputFile::open(file.as_ref()) {
+            Ok(input) => self.process_reader(&name, BufReader::new(input)),
+            Err(err) => {
+                self.input = name;
+                self.line = 0;
+                self.skip_unreadable_input(err)
+            }
+        }
+    }
+    fn process_reader<R: BufRead>(&mut self, name: &str, reader: R) -> Result<(), MergeError> {
+        self.input = name.to_string();
+        self.line = 0;
+        self.test_name = None;
+        self.source_name = None;
+        self.excluded = false;
+        self.function_locations.clear();
+        self.tests = Tests::new();
 
-        loop {
-            let result = try!(parser.next());
+        let mut reader = match decompress(reader) {
+            Ok(reader) => reader,
+            Err(err) => return self.skip_unreadable_input(err)
+        };
+        match first_byte(&mut reader) {
+            Ok(Some(b'{')) => return self.process_json(reader),
+            Ok(Some(b'<')) => return self.process_cobertura(reader),
+            Ok(_) => {},
+            Err(err) => return self.skip_unreadable_input(err)
+        }
 
-            if result.is_none() {
+        let mut bytes = vec![];
+        let mut line = String::new();
+        loop {
+            bytes.clear();
+            let size = match reader.read_until(b'\n', &mut bytes) {
+                Ok(size) => size,
+                Err(err) => {
+                    if !self.lenient {
+                        return Err(MergeError::from(err));
+                    }
+                    let reason = format!("read error, the rest of the input is skipped: {}", err);
+                    self.skip_block(reason);
+                    return Ok(());
+                }
+            };
+            if size == 0 {
                 break;
             }
-            let record = result.unwrap();
-
-            match record {
-                LCOVRecord::TestName(ref name) => self.on_test_name(name),
-                LCOVRecord::SourceFile(ref name) => self.on_source_file(name),
-                LCOVRecord::Data(ref data) => try!(self.on_data(data)),
-                LCOVRecord::FunctionName(ref func_name) => try!(self.on_func_name(func_name)),
-                LCOVRecord::FunctionData(ref func_data) => try!(self.on_func_data(func_data)),
-                LCOVRecord::BranchData(ref branch_data) => try!(self.on_branch_data(branch_data)),
-                LCOVRecord::EndOfRecord => try!(self.on_end_of_record()),
-                _ => { continue; }
+            self.line += 1;
+
+            line.clear();
+            match str::from_utf8(&bytes) {
+                Ok(text) => line.push_str(text),
+                Err(_) => {
+                    if !self.lenient {
+                        return Err(MergeError::from(IOError::new(ErrorKind::InvalidData, "stream did not contain valid UTF-8")));
+                    }
+                    self.skip_rest_of_block("invalid UTF-8, the block is skipped");
+                    continue;
+                }
+            }
+
+            // Written tracefiles end with a blank line.
+            if line.trim_end().is_empty() {
+                continue;
+            }
+            if self.excluded && !is_block_boundary(&line) {
+                continue;
+            }
+            if !line.ends_with('\n') {
+                line.push('\n');
+            }
+            let record = match read_record(&line) {
+                Ok(record) => record,
+                Err(err) => {
+                    let error = RecordParseError {
+                        line: self.line,
+                        column: err.column,
+                        message: err.message
+                    };
+                    if !self.lenient {
+                        return Err(MergeError::RecordParse(error));
+                    }
+                    let reason = format!("malformed record skipped: {}", parse_error_details(&error));
+                    self.diagnose(reason);
+                    continue;
+                }
             };
+            if let Err(err) = self.on_record(&record) {
+                if !self.lenient {
+                    return Err(err);
+                }
+                self.skip_rest_of_block(format!("merge error, the block is skipped: {}", err));
+            }
+        }
+
+        if self.lenient && self.source_name.is_some() {
+            self.skip_block("missing end_of_record, the unterminated block is skipped");
+        }
+        Ok(())
+    }
+    /// Merge a JSON input, which is told apart by its top level keys.
+    fn process_json<R: BufRead>(&mut self, reader: R) -> Result<(), MergeError> {
+        let value: Value = match serde_json::from_reader(reader) {
+            Ok(value) => value,
+            Err(err) => {
+                if err.is_io() {
+                    if !self.lenient {
+                        return Err(MergeError::from(IOError::from(err)));
+                    }
+                    return self.skip_input(format!("read error, the input is skipped: {}", err));
+                }
+                self.line = err.line() as u32;
+                return self.skip_input(format!("invalid JSON report, the input is skipped: {}", err));
+            }
+        };
+        if value.get("format_version").is_some() || value.get("gcc_version").is_some() {
+            return self.process_gcov(value);
+        }
+        if value.get("type").and_then(Value::as_str) == Some(LLVM_EXPORT_TYPE) {
+            return self.process_llvm(value);
+        }
+        self.process_json_report(value)
+    }
+    /// Merge the JSON representation of a report, one block per test of each source file.
+    fn process_json_report(&mut self, value: Value) -> Result<(), MergeError> {
+        let report = match JsonReport::from_value(value) {
+            Ok(report) => report,
+            Err(err) => {
+                return self.skip_input(format!("invalid JSON report, the input is skipped: {}", err));
+            }
+        };
+        for (source_name, file) in report.files.iter() {
+            for (test_name, test) in file.tests.iter() {
+                self.on_test_name(&Some(test_name.clone()));
+                self.on_source_file(source_name);
+                if !self.excluded {
+                    for line in test.lines.iter() {
+                        try!(self.on_data(&LineData { line: line.line, count: line.count, checksum: line.checksum.clone() }));
+                    }
+                    for function in test.functions.iter() {
+                        let record = Function::new(function.name.clone(), function.line, function.count)
+                            .with_end_line_number(function.end_line);
+                        try!(self.on_function(&record));
+                    }
+                    for branch in test.branches.iter() {
+                        let record = match branch.taken {
+                            Some(taken) => Branch::new(branch.line, branch.block, branch.branch, taken),
+                            None => Branch::not_evaluated(branch.line, branch.block, branch.branch)
+                        };
+                        try!(self.on_branch(&record));
+                    }
+                }
+                try!(self.on_end_of_record());
+            }
+        }
+        Ok(())
+    }
+    /// Merge an intermediate file of `gcov --json-format`, one block per source file with an unnamed test.
+    fn process_gcov(&mut self, value: Value) -> Result<(), MergeError> {
+        let report = match GcovReport::from_value(value) {
+            Ok(report) => report,
+            Err(err) => {
+                return self.skip_input(format!("invalid gcov JSON, the input is skipped: {}", err));
+            }
+        };
+        for file in report.files.iter() {
+            self.on_test_name(&None);
+            self.on_source_file(&report.source_file(file));
+            if !self.excluded {
+                for line in file.to_lines().iter() {
+                    try!(self.on_data(line));
+                }
+                for function in file.to_functions().iter() {
+                    try!(self.on_function(function));
+                }
+                for branch in file.to_branches().iter() {
+                    try!(self.on_branch(branch));
+                }
+            }
+            try!(self.on_end_of_record());
+        }
+        Ok(())
+    }
+    /// Merge the JSON of `llvm-cov export`, one block per source file of each export with an unnamed test.
+    fn process_llvm(&mut self, value: Value) -> Result<(), MergeError> {
+        let report = match LlvmReport::from_value(value) {
+            Ok(report) => report,
+            Err(err) => {
+                return self.skip_input(format!("invalid llvm-cov export, the input is skipped: {}", err));
+            }
+        };
+        for export in report.data.iter() {
+            for file in export.files.iter() {
+                self.on_test_name(&None);
+                self.on_source_file(&file.filename);
+                if !self.excluded {
+                    for line in file.to_lines().iter() {
+                        try!(self.on_data(line));
+                    }
+                    for function in export.functions_of(file).iter() {
+                        try!(self.on_function(function));
+                    }
+                    for branch in file.to_branches().iter() {
+                        try!(self.on_branch(branch));
+                    }
+                }
+                try!(self.on_end_of_record());
+            }
+        }
+        Ok(())
+    }
+    /// Merge a Cobertura XML report, one block per class with an unnamed test.
+    fn process_cobertura<R: BufRead>(&mut self, reader: R) -> Result<(), MergeError> {
+        let report = match CoberturaReport::from_reader(reader) {
+            Ok(report) => report,
+            Err(err) => {
+                self.line = err.line;
+                return self.skip_input(format!("invalid Cobertura report, the input is skipped: {}", err.message));
+            }
+        };
+        for class in report.classes.iter() {
+            self.on_test_name(&None);
+            self.on_source_file(&report.source_file(class));
+            if !self.excluded {
+                for line in class.lines.iter() {
+                    try!(self.on_data(line));
+                }
+                for function in class.functions.iter() {
+                    try!(self.on_function(function));
+                }
+                for branch in class.branches.iter() {
+                    try!(self.on_branch(branch));
+                }
+            }
+            try!(self.on_end_of_record());
         }
         Ok(())
     }
+    /// Fail on an input that can't be read, or skip it with a diagnostic in lenient mode.
+    fn skip_input(&mut self, reason: String) -> Result<(), MergeError> {
+        if !self.lenient {
+            return Err(MergeError::InvalidRecord(
+                Diagnostic::new(self.input.clone(), self.line, reason)
+            ));
+        }
+        self.diagnose(reason);
+        Ok(())
+    }
+    /// Fail on an input that can't be opened or read, or skip it with a diagnostic in lenient mode.
+    fn skip_unreadable_input(&mut self, err: IOError) -> Result<(), MergeError> {
+        if !self.lenient {
+            return Err(MergeError::from(IOError::new(err.kind(), format!("{}: {}", self.input, err))));
+        }
+        self.skip_input(format!("the input can't be read and is skipped: {}", err))
+    }
+    fn on_record(&mut self, record: &Record) -> Result<(), MergeError> {
+        match *record {
+            Record::Lcov(LCOVRecord::TestName(ref name)) => self.on_test_name(name),
+            Record::Lcov(LCOVRecord::SourceFile(ref name)) => self.on_source_file(name),
+            Record::Lcov(LCOVRecord::Data(ref data)) => try!(self.on_data(data)),
+            Record::Lcov(LCOVRecord::FunctionName(ref func_name)) => try!(self.on_func_name(func_name)),
+            Record::Lcov(LCOVRecord::FunctionData(ref func_data)) => try!(self.on_func_data(func_data)),
+            Record::Lcov(LCOVRecord::EndOfRecord) => try!(self.on_end_of_record()),
+            Record::Branch(ref branch) => try!(self.on_branch(branch)),
+            Record::Function(ref function) => try!(self.on_function(function)),
+            Record::FunctionLocation(index, line_number, end_line_number) => {
+                self.function_locations.insert(index, (line_number, end_line_number));
+            },
+            Record::FunctionAlias(index, execution_count, ref name) => {
+                try!(self.on_func_alias(index, execution_count, name))
+            },
+            _ => {}
+        };
+        Ok(())
+    }
+    fn diagnose<T: Into<String>>(&mut self, reason: T) {
+        let diagnostic = Diagnostic::new(self.input.clone(), self.line, reason);
+        self.diagnostics.push(diagnostic);
+    }
+    fn skip_block<T: Into<String>>(&mut self, reason: T) {
+        self.diagnose(reason);
+        self.source_name = None;
+        self.excluded = false;
+        self.function_locations.clear();
+        self.tests = Tests::new();
+    }
+    /// Skip the rest of the block up to its `end_of_record`, or the whole block once it has ended.
+    fn skip_rest_of_block<T: Into<String>>(&mut self, reason: T) {
+        if self.source_name.is_none() {
+            return self.skip_block(reason);
+        }
+        self.diagnose(reason);
+        self.tests = Tests::new();
+        self.excluded = true;
+    }
 
     fn on_test_name(&mut self, test_name: &Option<String>) {
-        self.test_name = match test_name {
-            &Some(ref name) => Some(name.clone()),
-            &None => Some(String::new())
+        let test_name = match test_name {
+            &Some(ref name) => name.as_str(),
+            &None => ""
         };
+        self.test_name = Some(self.test_naming.test_name(&self.input, test_name));
         let current_test_name = self.test_name.as_ref().unwrap();
         self.tests.merge(current_test_name);
     }
     fn on_source_file(&mut self, source_name: &String) {
-        self.source_name = Some(source_name.clone());
+        if self.lenient && self.source_name.is_some() {
+            self.skip_block("missing end_of_record, the previous block is skipped");
+        }
+        if self.test_name.is_none() {
+            self.on_test_name(&None);
+        }
+        let source_name = match self.path_normalizer {
+            Some(ref normalizer) => normalizer.normalize(source_name),
+            None => source_name.clone()
+        };
+        self.excluded = match self.source_filter {
+            Some(ref filter) => !filter.matches(&source_name),
+            None => false
+        };
+        self.source_name = Some(source_name);
     }
     fn on_data(&mut self, line_data: &LineData) -> MergeResult<ChecksumError> {
-        if self.test_name.is_some() {
-            let test_name = self.test_name.as_ref().unwrap();
-            try!(self.tests.try_merge((test_name, line_data)));
+        let test_name = match self.test_name {
+            Some(ref test_name) => test_name,
+            None => return Ok(())
+        };
+        let source_name = match self.source_name {
+            Some(ref source_name) => source_name,
+            None => return self.tests.try_merge((test_name, line_data))
+        };
+        self.tests.merge(test_name);
+        if let Some(test) = self.tests.get_mut(test_name) {
+            if !try!(self.resolver.merge_line(source_name, test_name, test.lines_mut(), line_data)) {
+                self.excluded = true;
+            }
         }
         Ok(())
     }
@@ -94,32 +545,101 @@ impl ReportMerger {
         try!(self.tests.try_merge((test_name, func_data)));
         Ok(())
     }
-    fn on_branch_data(&mut self, branch_data: &BranchDataRecord) -> MergeResult<BranchError> {
+    fn on_function(&mut self, function: &Function) -> MergeResult<FunctionError> {
         if self.test_name.is_none() {
             return Ok(());
         }
         let test_name = self.test_name.as_ref().unwrap();
-        try!(self.tests.try_merge((test_name, branch_data)));
+        try!(self.tests.try_merge((test_name, function)));
         Ok(())
     }
-    fn on_end_of_record(&mut self) -> MergeResult<TestError> {
-        let source_name = self.source_name.as_ref().unwrap();
-        let file = File::new(self.tests.clone());
-
-        try!(self.files.try_merge((source_name, &file)));
+    fn on_func_alias(&mut self, index: u32, execution_count: ExecutionCount, name: &str) -> Result<(), MergeError> {
+        let (line_number, end_line_number) = match self.function_locations.get(&index) {
+            Some(&location) => location,
+            None => {
+                let reason = format!("FNA without FNL for the index {}, the record is skipped", index);
+                if !self.lenient {
+                    return Err(MergeError::InvalidRecord(
+                        Diagnostic::new(self.input.clone(), self.line, reason)
+                    ));
+                }
+                self.diagnose(reason);
+                return Ok(());
+            }
+        };
+        let function = Function::new(name.to_string(), line_number, execution_count)
+            .with_end_line_number(end_line_number);
+        try!(self.on_function(&function));
+        Ok(())
+    }
+    fn on_branch(&mut self, branch: &Branch) -> MergeResult<BranchError> {
+        if self.test_name.is_none() {
+            return Ok(());
+        }
+        let test_name = self.test_name.as_ref().unwrap();
+        try!(self.tests.try_merge((test_name, branch)));
+        Ok(())
+    }
+    fn on_end_of_record(&mut self) -> Result<(), MergeError> {
+        let source_name = match self.source_name.take() {
+            Some(source_name) => source_name,
+            None => {
+                let reason = "end_of_record without SF, the block is skipped";
+                if !self.lenient {
+                    return Err(MergeError::InvalidRecord(
+                        Diagnostic::new(self.input.clone(), self.line, reason)
+                    ));
+                }
+                self.skip_block(reason);
+                return Ok(());
+            }
+        };
+        if !self.excluded {
+            let file = File::new(self.tests.clone());
+            try!(self.resolver.merge(&mut self.files, &source_name, file));
+        }
+        self.excluded = false;
+        self.function_locations.clear();
         self.tests = Tests::new();
         Ok(())
     }
 }
 
+/// First non-whitespace byte of the input, which tells a JSON (`{`) or XML (`<`) report from a tracefile
+fn first_byte<R: BufRead + ?Sized>(reader: &mut R) -> Result<Option<u8>, IOError> {
+    let buffer = try!(reader.fill_buf());
+    Ok(buffer.iter().find(|byte| !byte.is_ascii_whitespace()).cloned())
+}
+
+/// Whether the line starts or ends a block, which are the only records read in a filtered out block.
+fn is_block_boundary(line: &str) -> bool {
+    line.starts_with("SF:") || line.trim_end() == "end_of_record"
+}
+
 
 #[cfg(test)]
 mod tests {
     use merger::*;
+    use merger::ops:: { MergeError };
+    use merger::conflict:: { ConflictPolicy, ChecksumMode };
+    use merger::naming:: { TestNaming };
+    use merger::path:: { PathNormalizer };
+    use merger::filter:: { SourceFilter };
+    use report::summary:: { Summary };
+    use report::coverage:: { Coverage };
+    use report::function:: { FunctionFormat };
+    use report::test:: { Test };
     use std::path::Path;
     use std::fs::File;
     use std::io::*;
 
+    fn read_fixture(path: &str) -> String {
+        let mut output = String::new();
+        let mut f = File::open(path).unwrap();
+        let _ = f.read_to_string(&mut output);
+        output
+    }
+
     #[test]
     fn save_as() {
         let report_path = "tests/fixtures/fixture1.info";
@@ -129,6 +649,7 @@ mod tests {
         let _ = report.save_as("/tmp/report.lcov").unwrap();
 
         assert_eq!(Path::new("/tmp/report.lcov").exists(), true);
+        assert_eq!(read_fixture("/tmp/report.lcov"), format!("{}\n", report));
     }
 
     #[test]
@@ -145,4 +666,532 @@ mod tests {
 
         assert_eq!(report.to_string(), readed_file_content);
     }
+
+    #[test]
+    fn save_as_compressed() {
+        let report_path = "tests/fixtures/fixture1.info";
+
+        for output in ["/tmp/report.lcov.gz", "/tmp/report.lcov.zst"].iter() {
+            let mut parse = ReportMerger::new();
+            let report = parse.merge(&[ report_path ]).unwrap();
+            let _ = report.save_as(output).unwrap();
+
+            let mut compressed = Vec::new();
+            let _ = File::open(output).unwrap().read_to_end(&mut compressed);
+            assert!(compressed != read_fixture(report_path).into_bytes());
+
+            let mut parse = ReportMerger::new();
+            let merged = parse.merge(&[ output ]).unwrap();
+            assert_eq!(merged.to_string(), report.to_string());
+        }
+    }
+
+    #[test]
+    fn merge_parallel() {
+        let report_paths = [
+            "tests/fixtures/fixture1.info",
+            "tests/fixtures/fixture2.info",
+            "tests/fixtures/fixture1.info",
+            "tests/fixtures/fixture2.info"
+        ];
+        let sequential = ReportMerger::new().merge(&report_paths).unwrap();
+        let parallel = ReportMerger::new().merge_parallel(&report_paths, 3).unwrap();
+
+        assert_eq!(parallel.to_string(), sequential.to_string());
+    }
+
+    #[test]
+    fn merge_parallel_conflict_policy() {
+        let inputs = [
+            ("/tmp/conflict1.info", "TN:\nSF:/path/to/source.c\nDA:1,1,aaa\nDA:2,1\nend_of_record\n"),
+            ("/tmp/conflict2.info", "TN:\nSF:/path/to/source.c\nDA:1,2,bbb\nDA:2,1\nend_of_record\n"),
+            ("/tmp/conflict3.info", "TN:\nSF:/path/to/source.c\nDA:1,3,bbb\nDA:2,1\nend_of_record\n"),
+            ("/tmp/conflict4.info", "TN:\nSF:/path/to/source.c\nDA:1,5,aaa\nDA:2,1\nend_of_record\n")
+        ];
+        for &(path, content) in inputs.iter() {
+            File::create(path).unwrap().write_all(content.as_bytes()).unwrap();
+        }
+        let report_paths: Vec<&str> = inputs.iter().map(|&(path, _)| path).collect();
+
+        let policies = [
+            ConflictPolicy::Error,
+            ConflictPolicy::KeepFirst,
+            ConflictPolicy::KeepLast,
+            ConflictPolicy::DropRecord,
+            ConflictPolicy::DropLine
+        ];
+        for policy in policies.iter() {
+            let sequential = ReportMerger::new().conflict_policy(*policy).merge(&report_paths);
+            let parallel = ReportMerger::new().conflict_policy(*policy).merge_parallel(&report_paths, 4);
+            match (sequential, parallel) {
+                (Ok(sequential), Ok(parallel)) => {
+                    assert_eq!(parallel.to_string(), sequential.to_string(), "{:?}", policy);
+                    assert_eq!(parallel.conflicts(), sequential.conflicts(), "{:?}", policy);
+                },
+                (Err(_), Err(_)) => assert_eq!(*policy, ConflictPolicy::Error),
+                _ => panic!("{:?}: only one of the merges failed", policy)
+            }
+        }
+    }
+
+    #[test]
+    fn merge_reader() {
+        let content = read_fixture("tests/fixtures/fixture1.info");
+
+        let mut parse = ReportMerger::new();
+        let report = parse.merge_reader(Cursor::new(content.as_bytes())).unwrap();
+
+        assert_eq!(report.to_string(), content);
+    }
+
+    #[test]
+    fn merge_readers() {
+        let fixture1 = read_fixture("tests/fixtures/fixture1.info");
+        let fixture2 = read_fixture("tests/fixtures/fixture2.info");
+        let readers = vec![
+            Cursor::new(fixture1.as_bytes()),
+            Cursor::new(fixture2.as_bytes())
+        ];
+
+        let mut parse = ReportMerger::new();
+        let report = parse.merge_readers(readers).unwrap();
+
+        assert_eq!(report.to_string(), read_fixture("tests/fixtures/merged_fixture.info"));
+    }
+
+    #[test]
+    fn lenient_skips_unmerged_blocks() {
+        let mut input = b"SF:/a.c\nDA:1,1,aaa\nDA:1,2,bbb\nDA:2,1\nend_of_record\n".to_vec();
+        input.extend_from_slice(b"SF:/b.c\nDA:1,1\nDA:\xff\nDA:2,1\nend_of_record\n");
+        input.extend_from_slice(b"SF:/c.c\nDA:1,1,ccc\nend_of_record\nSF:/c.c\nDA:1,1,xxx\nend_of_record\n");
+        input.extend_from_slice(b"SF:/d.c\nDA:1,1\nend_of_record\n");
+
+        let report = ReportMerger::new().lenient(true).merge_reader(Cursor::new(input.clone())).unwrap();
+
+        let lines: Vec<u32> = report.diagnostics().iter().map(|diagnostic| diagnostic.line).collect();
+        assert_eq!(lines, vec![3, 8, 16]);
+        assert!(report.diagnostics()[0].to_string().contains("checksum mismatch at line 1: aaa != bbb"));
+        assert!(report.diagnostics()[1].to_string().contains("invalid UTF-8"));
+        assert!(report.get("/a.c").is_none());
+        assert!(report.get("/b.c").is_none());
+        assert_eq!(report.get("/c.c").unwrap().tests().get(&"".to_string()).unwrap().lines().len(), 1);
+        assert!(report.get("/d.c").is_some());
+
+        assert!(ReportMerger::new().merge_reader(Cursor::new(input)).is_err());
+    }
+
+    #[test]
+    fn lenient_skips_malformed_records() {
+        let fixture1 = read_fixture("tests/fixtures/fixture1.info");
+        let broken = "TN:broken\nSF:/path/to/broken.c\nDA:1\nDA:2,1\nend_of_record\nend_of_record\nSF:/path/to/truncated.c\nDA:1,1\n";
+        let readers = vec![
+            ("fixture1.info".to_string(), Cursor::new(fixture1.as_bytes())),
+            ("broken.info".to_string(), Cursor::new(broken.as_bytes()))
+        ];
+
+        let mut parse = ReportMerger::new();
+        let report = parse.lenient(true).merge_named_readers(readers).unwrap();
+
+        assert_eq!(report.diagnostics().len(), 3);
+        assert_eq!(report.diagnostics()[0].input, "broken.info");
+        assert_eq!(report.diagnostics()[0].line, 3);
+        assert_eq!(report.diagnostics()[1].line, 6);
+        assert_eq!(report.diagnostics()[2].line, 8);
+
+        assert!(report.get("/path/to/broken.c").is_some());
+        assert!(report.get("/path/to/truncated.c").is_none());
+        assert!(report.get("/home/vagrant/shared/lcov-merge/tests/fixtures/fixture.c").is_some());
+    }
+
+    #[test]
+    fn lenient_skips_missing_inputs() {
+        let report_paths = [
+            "tests/fixtures/fixture1.info",
+            "tests/fixtures/missing.info",
+            "tests/fixtures/fixture2.info"
+        ];
+        let expected = ReportMerger::new().merge(&[ report_paths[0], report_paths[2] ]).unwrap();
+
+        let report = ReportMerger::new().lenient(true).merge(&report_paths).unwrap();
+        assert_eq!(report.to_string(), expected.to_string());
+        assert_eq!(report.diagnostics().len(), 1);
+        assert_eq!(report.diagnostics()[0].input, "tests/fixtures/missing.info");
+
+        let report = ReportMerger::new().lenient(true).merge_parallel(&report_paths, 2).unwrap();
+        assert_eq!(report.to_string(), expected.to_string());
+        assert_eq!(report.diagnostics().len(), 1);
+
+        match ReportMerger::new().merge(&report_paths) {
+            Err(err) => assert!(err.to_string().starts_with("tests/fixtures/missing.info: ")),
+            Ok(_) => panic!("a missing input is an error without lenient")
+        }
+    }
+
+    #[test]
+    fn end_of_record_without_source_file() {
+        let content = "TN:example\nDA:1,1\nend_of_record\n";
+
+        let mut parse = ReportMerger::new();
+        match parse.merge_reader(Cursor::new(content.as_bytes())) {
+            Err(MergeError::InvalidRecord(diagnostic)) => assert_eq!(diagnostic.line, 3),
+            _ => panic!("expected an invalid record error")
+        }
+    }
+
+    #[test]
+    fn conflict_policy() {
+        let first = "TN:\nSF:/path/to/source.c\nDA:1,1,abc\nDA:2,1,def\nend_of_record\n";
+        let last = "TN:\nSF:/path/to/source.c\nDA:1,1,abc\nDA:2,3,xyz\nend_of_record\n";
+
+        let mut parse = ReportMerger::new();
+        let report = parse
+            .conflict_policy(ConflictPolicy::KeepLast)
+            .merge_readers(vec![Cursor::new(first.as_bytes()), Cursor::new(last.as_bytes())])
+            .unwrap();
+
+        assert_eq!(report.conflicts().len(), 1);
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nDA:1,2,abc\nDA:2,3,xyz\nLF:2\nLH:2\nend_of_record\n");
+
+        let mut parse = ReportMerger::new();
+        let result = parse.merge_readers(vec![Cursor::new(first.as_bytes()), Cursor::new(last.as_bytes())]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn conflict_policy_in_block() {
+        let input = "TN:\nSF:/path/to/source.c\nDA:1,1,aaa\nDA:1,2,bbb\nDA:2,1\nend_of_record\n";
+        let merge = |policy| ReportMerger::new()
+            .conflict_policy(policy)
+            .merge_reader(Cursor::new(input.as_bytes()));
+
+        let report = merge(ConflictPolicy::KeepFirst).unwrap();
+        assert_eq!(report.conflicts().len(), 1);
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nDA:1,1,aaa\nDA:2,1\nLF:2\nLH:2\nend_of_record\n");
+
+        let report = merge(ConflictPolicy::KeepLast).unwrap();
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nDA:1,2,bbb\nDA:2,1\nLF:2\nLH:2\nend_of_record\n");
+
+        let report = merge(ConflictPolicy::DropLine).unwrap();
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nDA:2,1\nLF:1\nLH:1\nend_of_record\n");
+
+        let report = merge(ConflictPolicy::DropRecord).unwrap();
+        assert_eq!(report.len(), 0);
+
+        assert!(merge(ConflictPolicy::Error).is_err());
+    }
+
+    #[test]
+    fn checksum_mode_in_block() {
+        let input = "TN:\nSF:/path/to/source.c\nDA:1,1,aaa\nDA:1,2\nDA:2,1\nDA:2,1,bbb\nend_of_record\n";
+
+        assert!(ReportMerger::new().merge_reader(Cursor::new(input.as_bytes())).is_err());
+
+        let report = ReportMerger::new()
+            .checksum_mode(ChecksumMode::Mixed)
+            .merge_reader(Cursor::new(input.as_bytes()))
+            .unwrap();
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nDA:1,3,aaa\nDA:2,2,bbb\nLF:2\nLH:2\nend_of_record\n");
+    }
+
+    #[test]
+    fn without_test_name() {
+        let report_paths = [
+            "tests/fixtures/fixture1.info",
+            "tests/fixtures/without_test_name_fixture.info"
+        ];
+        let mut parse = ReportMerger::new();
+        let report = parse.merge(&report_paths).unwrap();
+
+        assert_eq!(report.to_string(), read_fixture("tests/fixtures/marged_without_test_name_fixture.info"));
+    }
+
+    #[test]
+    fn block_without_test_name() {
+        let content = "SF:/path/to/source.c\nDA:1,1\nend_of_record\n";
+
+        let mut parse = ReportMerger::new();
+        let report = parse.merge_reader(Cursor::new(content.as_bytes())).unwrap();
+
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nDA:1,1\nLF:1\nLH:1\nend_of_record\n");
+    }
+
+    #[test]
+    fn not_evaluated_branches() {
+        let first = "TN:\nSF:/path/to/source.c\nBRDA:1,0,0,-\nBRDA:1,0,1,-\nBRDA:1,0,2,-\nend_of_record\n";
+        let last = "TN:\nSF:/path/to/source.c\nBRDA:1,0,0,-\nBRDA:1,0,1,0\nBRDA:1,0,2,2\nend_of_record\n";
+
+        let mut parse = ReportMerger::new();
+        let report = parse.merge_readers(vec![Cursor::new(first.as_bytes()), Cursor::new(last.as_bytes())]).unwrap();
+
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nBRDA:1,0,0,-\nBRDA:1,0,1,0\nBRDA:1,0,2,2\nBRF:3\nBRH:1\nend_of_record\n");
+    }
+
+    #[test]
+    fn function_locations_and_aliases() {
+        let legacy = "TN:\nSF:/path/to/source.c\nFN:4,main\nFNDA:1,main\nend_of_record\n";
+        let lcov2 = "TN:\nSF:/path/to/source.c\nFNL:0,4,10\nFNA:0,2,main\nFNA:0,1,_main\nend_of_record\n";
+
+        let mut parse = ReportMerger::new();
+        let report = parse.merge_readers(vec![Cursor::new(legacy.as_bytes()), Cursor::new(lcov2.as_bytes())]).unwrap();
+
+        assert_eq!(report.to_string(), "TN:\nSF:/path/to/source.c\nFN:4,_main\nFNDA:1,_main\nFN:4,main\nFNDA:3,main\nFNF:2\nFNH:2\nend_of_record\n");
+        assert_eq!(report.records(FunctionFormat::Lcov2).to_string(), "TN:\nSF:/path/to/source.c\nFNL:0,4,10\nFNA:0,1,_main\nFNA:0,3,main\nFNF:1\nFNH:1\nend_of_record\n");
+    }
+
+    #[test]
+    fn function_alias_without_location() {
+        let input = "TN:\nSF:/path/to/source.c\nFNA:0,2,main\nend_of_record\n";
+
+        let mut parse = ReportMerger::new();
+        match parse.merge_reader(Cursor::new(input.as_bytes())) {
+            Err(MergeError::InvalidRecord(diagnostic)) => assert_eq!(diagnostic.line, 3),
+            _ => panic!("expected an invalid record error")
+        }
+
+        let report = ReportMerger::new().lenient(true).merge_reader(Cursor::new(input.as_bytes())).unwrap();
+        assert_eq!(report.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn test_naming() {
+        let first = "TN:unit\nSF:/path/to/source.c\nDA:1,1\nend_of_record\nTN:integration\nSF:/path/to/source.c\nDA:1,2\nDA:2,0\nend_of_record\n";
+        let last = "TN:unit\nSF:/path/to/source.c\nDA:2,1\nend_of_record\n";
+        let inputs = || vec![
+            ("shard1.info".to_string(), Cursor::new(first.as_bytes())),
+            ("shard2.info".to_string(), Cursor::new(last.as_bytes()))
+        ];
+
+        let report = ReportMerger::new()
+            .test_naming(TestNaming::Flatten("all".to_string()))
+            .merge_named_readers(inputs()).unwrap();
+        assert_eq!(report.to_string(), "TN:all\nSF:/path/to/source.c\nDA:1,3\nDA:2,1\nLF:2\nLH:2\nend_of_record\n");
+
+        let report = ReportMerger::new()
+            .test_naming(TestNaming::FromInput)
+            .merge_named_readers(inputs()).unwrap();
+        let test_names: Vec<_> = report.get("/path/to/source.c").unwrap().tests().iter().map(|(name, _)| name.clone()).collect();
+        assert_eq!(test_names, vec!["shard1".to_string(), "shard2".to_string()]);
+
+        let report = ReportMerger::new()
+            .test_naming(TestNaming::PrefixInput)
+            .merge_named_readers(inputs()).unwrap();
+        let test_names: Vec<_> = report.get("/path/to/source.c").unwrap().tests().iter().map(|(name, _)| name.clone()).collect();
+        assert_eq!(test_names, vec!["shard1_integration".to_string(), "shard1_unit".to_string(), "shard2_unit".to_string()]);
+    }
+
+    #[test]
+    fn path_normalizer() {
+        let vagrant = "TN:\nSF:/home/vagrant/shared/src/main.c\nDA:1,1\nend_of_record\n";
+        let ci = "TN:\nSF:/builds/xyz/src/lib/../main.c\nDA:1,2\nend_of_record\n";
+        let relative = "TN:\nSF:src/main.c\nDA:2,0\nend_of_record\n";
+
+        let mut normalizer = PathNormalizer::new();
+        normalizer
+            .substitute("^/home/[^/]+/shared/", "").unwrap()
+            .strip_prefix("/builds/xyz/")
+            .base_dir("/project");
+
+        let report = ReportMerger::new()
+            .path_normalizer(normalizer)
+            .merge_readers(vec![
+                Cursor::new(vagrant.as_bytes()),
+                Cursor::new(ci.as_bytes()),
+                Cursor::new(relative.as_bytes())
+            ]).unwrap();
+
+        assert_eq!(report.to_string(), "TN:\nSF:/project/src/main.c\nDA:1,3\nDA:2,0\nLF:2\nLH:1\nend_of_record\n");
+    }
+
+    #[test]
+    fn source_filter() {
+        let input = concat!(
+            "TN:\nSF:/usr/include/stdio.h\nDA:1,1\nnot a record\nend_of_record\n",
+            "TN:\nSF:/src/generated/parser.c\nDA:1,1\nend_of_record\n",
+            "TN:\nSF:/src/main.c\nDA:1,1\nend_of_record\n"
+        );
+        let mut filter = SourceFilter::new();
+        filter
+            .include("/src/*").unwrap()
+            .exclude("*/generated/*").unwrap();
+
+        let report = ReportMerger::new()
+            .source_filter(filter)
+            .merge_reader(Cursor::new(input.as_bytes())).unwrap();
+
+        assert_eq!(report.to_string(), "TN:\nSF:/src/main.c\nDA:1,1\nLF:1\nLH:1\nend_of_record\n");
+    }
+
+    #[test]
+    fn filter_report() {
+        let mut parse = ReportMerger::new();
+        let report = parse.merge(&vec!["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let mut filter = SourceFilter::new();
+        filter.exclude("*/func?.c").unwrap();
+
+        let filtered = report.filter(&filter);
+        assert_eq!(report.len(), 3);
+        assert_eq!(filtered.len(), 1);
+        assert!(filtered.get("/home/vagrant/shared/lcov-merge/tests/fixtures/fixture.c").is_some());
+    }
+
+    #[test]
+    fn coverage() {
+        let mut parse = ReportMerger::new();
+        let report = parse.merge(&vec!["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let summary = report.coverage();
+
+        let file = report.get("/home/vagrant/shared/lcov-merge/tests/fixtures/func1.c").unwrap();
+        let test = file.get_test(&"example".to_string()).unwrap();
+        assert_eq!(file.coverage(), test.coverage());
+        assert_eq!(summary.lines, Coverage::new(20, 19));
+        assert_eq!(summary.functions, Coverage::new(3, 3));
+        assert_eq!(summary.branches, Coverage::new(8, 6));
+    }
+
+    #[test]
+    fn merge_json_with_tracefiles() {
+        let mut parse = ReportMerger::new();
+        let expected = parse.merge(&vec!["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let json = ReportMerger::new().merge(&vec!["tests/fixtures/fixture2.info"]).unwrap().json().to_string();
+
+        let fixture1 = read_fixture("tests/fixtures/fixture1.info");
+        let report = ReportMerger::new().merge_readers(vec![
+            Cursor::new(fixture1.as_bytes()),
+            Cursor::new(json.as_bytes())
+        ]).unwrap();
+        assert_eq!(report.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn invalid_json() {
+        let input = "{ \"version\": 1,\n  \"files\": [] }";
+
+        match ReportMerger::new().merge_reader(Cursor::new(input.as_bytes())) {
+            Err(MergeError::InvalidRecord(diagnostic)) => assert!(diagnostic.reason.starts_with("invalid JSON report")),
+            _ => panic!("expected an invalid record error")
+        }
+        match ReportMerger::new().merge_reader(Cursor::new("{ \"version\": \"1\",\n  \"files\": [ }".as_bytes())) {
+            Err(MergeError::InvalidRecord(diagnostic)) => {
+                assert_eq!(diagnostic.line, 2);
+                assert!(diagnostic.reason.starts_with("invalid JSON report"));
+            },
+            _ => panic!("expected an invalid record error")
+        }
+        let report = ReportMerger::new().lenient(true).merge_reader(Cursor::new(input.as_bytes())).unwrap();
+        assert_eq!(report.diagnostics().len(), 1);
+        assert_eq!(report.len(), 0);
+    }
+
+    #[test]
+    fn merge_gcov_json() {
+        let func1 = "/home/vagrant/shared/lcov-merge/tests/fixtures/func1.c";
+        let expected = ReportMerger::new().merge(&vec!["tests/fixtures/fixture1.info"]).unwrap();
+        let report = ReportMerger::new().merge(&vec!["tests/fixtures/fixture-func1.gcov.json.gz"]).unwrap();
+
+        let expected_test = expected.get(func1).unwrap().flatten();
+        let test = report.get(func1).unwrap().tests().get(&"".to_string()).unwrap().clone();
+        assert_eq!(report.len(), 1);
+        assert_eq!(test.coverage(), expected_test.coverage());
+        let branch_counts = |test: &Test| -> Vec<(u32, u32, Option<u32>)> {
+            test.branches().get(&8).unwrap().iter()
+                .map(|(_, branch)| (*branch.block(), *branch.branch(), branch.execution_count().cloned()))
+                .collect()
+        };
+        assert_eq!(branch_counts(&test), branch_counts(&expected_test));
+        for (line_number, line) in expected_test.lines().iter() {
+            assert_eq!(test.lines().get(line_number).unwrap().execution_count(), line.execution_count());
+        }
+        assert_eq!(test.functions().get(&"func1".to_string()).unwrap().end_line_number(), Some(&15));
+    }
+
+    #[test]
+    fn invalid_gcov_json() {
+        let input = "{ \"format_version\": \"1\", \"gcc_version\": \"12.2.0\",\n  \"files\": {} }";
+
+        match ReportMerger::new().merge_reader(Cursor::new(input.as_bytes())) {
+            Err(MergeError::InvalidRecord(diagnostic)) => assert!(diagnostic.reason.starts_with("invalid gcov JSON")),
+            _ => panic!("expected an invalid record error")
+        }
+    }
+
+    #[test]
+    fn merge_llvm_export() {
+        let report = ReportMerger::new().merge(&vec!["tests/fixtures/fixture.llvm.json"]).unwrap();
+        let test = report.get("/src/lcov/main.rs").unwrap().tests().get(&"".to_string()).unwrap();
+
+        let lines: Vec<(u32, u32)> = test.lines().iter().map(|(line_number, line)| (*line_number, *line.execution_count())).collect();
+        assert_eq!(lines, vec![
+            (1, 2), (2, 2), (3, 2), (4, 1), (5, 1), (6, 2), (7, 1), (8, 1), (9, 2), (10, 2),
+            (12, 1), (13, 1), (14, 1), (15, 1)
+        ]);
+        let func1 = test.functions().get(&"_RNvCs1_4main5func1".to_string()).unwrap();
+        assert_eq!((*func1.line_number(), func1.end_line_number(), *func1.execution_count()), (1, Some(&10), 2));
+        assert_eq!(test.functions().len(), 2);
+        assert_eq!((test.coverage().branches.hit, test.coverage().branches.found), (4, 4));
+        assert_eq!(report.len(), 2);
+    }
+
+    #[test]
+    fn merge_clamped_counts() {
+        let gcov = concat!(
+            "{ \"format_version\": \"1\", \"gcc_version\": \"12.2.0\", \"files\": [{ \"file\": \"/src/main.c\",\n",
+            "  \"functions\": [{ \"name\": \"main\", \"start_line\": 1, \"execution_count\": 5000000000 }],\n",
+            "  \"lines\": [{ \"line_number\": 2, \"count\": 5000000000, \"branches\": [{ \"count\": 5000000000 }] }] }] }"
+        );
+        let llvm = concat!(
+            "{ \"type\": \"llvm.coverage.json.export\", \"version\": \"2.0.1\", \"data\": [{ \"files\": [{ \"filename\": \"/src/main.rs\",\n",
+            "  \"segments\": [[1, 1, 5000000000, true, true, false], [2, 2, 0, false, false, false]],\n",
+            "  \"branches\": [[1, 5, 1, 9, 5000000000, 0, 0, 0, 4]] }] }] }"
+        );
+        let report = ReportMerger::new().merge_readers(vec![
+            Cursor::new(gcov.as_bytes()), Cursor::new(gcov.as_bytes()),
+            Cursor::new(llvm.as_bytes()), Cursor::new(llvm.as_bytes())
+        ]).unwrap();
+
+        let main_c = report.get("/src/main.c").unwrap().tests().get(&"".to_string()).unwrap();
+        assert_eq!(main_c.lines().get(&2).unwrap().execution_count(), &u32::MAX);
+        assert_eq!(main_c.functions().get(&"main".to_string()).unwrap().execution_count(), &u32::MAX);
+        assert_eq!(main_c.branches().get(&2).unwrap().iter().next().unwrap().1.execution_count(), Some(&u32::MAX));
+
+        let main_rs = report.get("/src/main.rs").unwrap().tests().get(&"".to_string()).unwrap();
+        assert_eq!(main_rs.lines().get(&1).unwrap().execution_count(), &u32::MAX);
+        assert_eq!(main_rs.branches().get(&1).unwrap().iter().next().unwrap().1.execution_count(), Some(&u32::MAX));
+    }
+
+    #[test]
+    fn merge_cobertura_with_tracefiles() {
+        let fixture1 = read_fixture("tests/fixtures/fixture1.info");
+        let xml = concat!(
+            "<?xml version=\"1.0\" ?>\n<coverage><sources><source>/home/vagrant/shared/lcov-merge</source></sources>\n",
+            "<packages><package name=\"tests.fixtures\"><classes>\n",
+            "<class name=\"func1.c\" filename=\"tests/fixtures/func1.c\">\n",
+            "<methods><method name=\"func1\" signature=\"\"><lines><line number=\"1\" hits=\"2\"/></lines></method></methods>\n",
+            "<lines><line number=\"1\" hits=\"2\"/><line number=\"3\" hits=\"2\" branch=\"true\" condition-coverage=\"50% (1/2)\"/></lines>\n",
+            "</class></classes></package></packages></coverage>\n"
+        );
+        let report = ReportMerger::new().merge_readers(vec![
+            Cursor::new(fixture1.as_bytes()),
+            Cursor::new(xml.as_bytes())
+        ]).unwrap();
+
+        let file = report.get("/home/vagrant/shared/lcov-merge/tests/fixtures/func1.c").unwrap();
+        let test = file.tests().get(&"".to_string()).unwrap();
+        assert_eq!(test.lines().get(&1).unwrap().execution_count(), &2);
+        assert_eq!(test.functions().get(&"func1".to_string()).unwrap().execution_count(), &2);
+        assert_eq!(test.branches().get(&3).unwrap().len(), 2);
+        assert!(file.tests().get(&"example".to_string()).is_some());
+    }
+
+    #[test]
+    fn invalid_cobertura() {
+        let input = "<coverage>\n<class filename=\"a.c\"><lines><line number=\"x\" hits=\"1\"/></lines></class></coverage>";
+
+        match ReportMerger::new().merge_reader(Cursor::new(input.as_bytes())) {
+            Err(MergeError::InvalidRecord(diagnostic)) => assert_eq!(diagnostic.line, 2),
+            _ => panic!("expected an invalid record error")
+        }
+        let report = ReportMerger::new().lenient(true).merge_reader(Cursor::new(input.as_bytes())).unwrap();
+        assert_eq!(report.diagnostics().len(), 1);
+        assert_eq!(report.len(), 0);
+    }
 }
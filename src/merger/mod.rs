@@ -1,9 +1,16 @@
 mod merger;
+mod parallel;
+mod record;
 pub mod ops;
+pub mod conflict;
+pub mod naming;
+pub mod path;
+pub mod filter;
 
 pub use merger::merger:: { ReportMerger };
 
 use std::path::Path;
+use std::io:: { BufRead };
 use report::*;
 use self::ops::MergeError;
 
@@ -11,3 +18,9 @@ pub fn merge_files<T: AsRef<Path>>(files: &[T]) -> Result<Report, MergeError> {
     let mut merger = ReportMerger::new();
     merger.merge(files)
 }
+
+pub fn merge_readers<I, R>(readers: I) -> Result<Report, MergeError>
+    where I: IntoIterator<Item=R>, R: BufRead {
+    let mut merger = ReportMerger::new();
+    merger.merge_readers(readers)
+}
@@ -0,0 +1,90 @@
+use std::path::Path;
+use std::str:: { FromStr };
+use report::attribute:: { TestName };
+
+/// How to name the tests of the merged report
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum TestNaming {
+    /// Keep the test names of the tracefiles
+    #[default]
+    Keep,
+    /// Collapse every test into a single test of the name (`lcov -t`)
+    Flatten(TestName),
+    /// Name the tests after the input file, so each input becomes a single test
+    FromInput,
+    /// Prefix the test names with the name of the input file
+    PrefixInput
+}
+
+impl TestNaming {
+    /// Name of the test `test_name` read from the input `input`
+    pub fn test_name(&self, input: &str, test_name: &str) -> TestName {
+        match *self {
+            TestNaming::Keep => test_name.to_string(),
+            TestNaming::Flatten(ref name) => name.clone(),
+            TestNaming::FromInput => {
+                let name = input_name(input);
+                if name.is_empty() { test_name.to_string() } else { name }
+            },
+            TestNaming::PrefixInput => {
+                let name = input_name(input);
+                match (name.is_empty(), test_name.is_empty()) {
+                    (true, _) => test_name.to_string(),
+                    (false, true) => name,
+                    (false, false) => format!("{}_{}", name, test_name)
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for TestNaming {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "keep" => Ok(TestNaming::Keep),
+            "flatten" => Ok(TestNaming::Flatten(String::new())),
+            "input" => Ok(TestNaming::FromInput),
+            "prefix" => Ok(TestNaming::PrefixInput),
+            _ => Err(format!("unknown test naming '{}'", value))
+        }
+    }
+}
+
+/// Name of the input usable as a test name: the file name without its extensions,
+/// where every character other than a letter, a digit or `_` is replaced with `_`.
+fn input_name(input: &str) -> String {
+    let file_name = Path::new(input).file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = file_name.split('.').next().unwrap_or("");
+    let name: String = stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    name.trim_matches('_').to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use merger::naming:: { TestNaming };
+
+    #[test]
+    fn test_name() {
+        assert_eq!(TestNaming::Keep.test_name("shard1.info", "unit"), "unit");
+        assert_eq!(TestNaming::Flatten("all".to_string()).test_name("shard1.info", "unit"), "all");
+        assert_eq!(TestNaming::FromInput.test_name("out/shard-1.info.gz", "unit"), "shard_1");
+        assert_eq!(TestNaming::PrefixInput.test_name("out/shard-1.info.gz", "unit"), "shard_1_unit");
+        assert_eq!(TestNaming::PrefixInput.test_name("shard1.info", ""), "shard1");
+        assert_eq!(TestNaming::FromInput.test_name("-", "unit"), "unit");
+        assert_eq!(TestNaming::FromInput.test_name("<reader 2>", ""), "reader_2");
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("flatten".parse(), Ok(TestNaming::Flatten(String::new())));
+        assert_eq!("prefix".parse(), Ok(TestNaming::PrefixInput));
+        assert!("rename".parse::<TestNaming>().is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use std::result::Result;
 use std::convert::From;
+use std::error::Error;
+use std::fmt:: { Display, Formatter, Result as FormatResult };
 use std::io:: { Error as IOError};
 use lcov_parser:: { ParseError, RecordParseError, BranchData };
 use report::line:: { Line };
 use report::branch:: { Branch };
 use report::attribute:: { LineNumber, FunctionName, CheckSum };
+use report::diagnostic:: { Diagnostic };
 
 pub type MergeResult<E> = Result<(), E>;
 
@@ -23,12 +26,34 @@ pub enum ChecksumError {
     Mismatch(MergeLine, MergeLine)
 }
 
+impl Display for ChecksumError {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            ChecksumError::Empty(ref line) => {
+                write!(f, "line {} has no checksum", line.line)
+            },
+            ChecksumError::Mismatch(ref left, ref right) => {
+                write!(f, "checksum mismatch at line {}: {} != {}", left.line, left, right)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct MergeLine {
     pub line: LineNumber,
     pub checksum: Option<CheckSum>
 }
 
+impl Display for MergeLine {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match self.checksum {
+            Some(ref checksum) => write!(f, "{}", checksum),
+            None => write!(f, "(none)")
+        }
+    }
+}
+
 impl<'a> From<&'a Line> for MergeLine {
     fn from(line: &'a Line) -> Self {
         let line_number = line.line_number().clone();
@@ -48,6 +73,16 @@ pub enum FunctionError {
     Mismatch(FunctionName, FunctionName)
 }
 
+impl Display for FunctionError {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            FunctionError::Mismatch(ref left, ref right) => {
+                write!(f, "function name mismatch: {} != {}", left, right)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct MergeBranch {
     pub line: LineNumber,
@@ -55,6 +90,12 @@ pub struct MergeBranch {
     pub branch: u32
 }
 
+impl Display for MergeBranch {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        write!(f, "{},{},{}", self.line, self.block, self.branch)
+    }
+}
+
 impl<'a> From<&'a Branch> for MergeBranch {
     fn from(branch: &'a Branch) -> Self {
         let line = branch.line_number();
@@ -83,6 +124,16 @@ pub enum BranchError {
     Mismatch(MergeBranch, MergeBranch)
 }
 
+impl Display for BranchError {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            BranchError::Mismatch(ref left, ref right) => {
+                write!(f, "branch mismatch: {} != {}", left, right)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum TestError {
     Checksum(ChecksumError),
@@ -90,6 +141,16 @@ pub enum TestError {
     Branch(BranchError)
 }
 
+impl Display for TestError {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            TestError::Checksum(ref error) => write!(f, "{}", error),
+            TestError::Function(ref error) => write!(f, "{}", error),
+            TestError::Branch(ref error) => write!(f, "{}", error)
+        }
+    }
+}
+
 impl From<ChecksumError> for TestError {
     fn from(error: ChecksumError) -> Self {
         TestError::Checksum(error)
@@ -112,9 +173,37 @@ impl From<BranchError> for TestError {
 pub enum MergeError {
     IO(IOError),
     RecordParse(RecordParseError),
+    InvalidRecord(Diagnostic),
     Process(TestError)
 }
 
+/// Reason of the parse error without the position reported by the parser combinator
+pub fn parse_error_details(error: &RecordParseError) -> String {
+    let message = error.message.replace("`\n`", "`\\n`");
+    let details: Vec<&str> = message.lines()
+        .skip(1)
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    details.join(", ")
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            MergeError::IO(ref error) => write!(f, "{}", error),
+            MergeError::RecordParse(ref error) => {
+                write!(f, "parse error at line {}, column {}: {}", error.line, error.column, parse_error_details(error))
+            },
+            MergeError::InvalidRecord(ref diagnostic) => write!(f, "{}", diagnostic),
+            MergeError::Process(ref error) => write!(f, "{}", error)
+        }
+    }
+}
+
+impl Error for MergeError {
+}
+
 impl From<IOError> for MergeError {
     fn from(error: IOError) -> Self {
         MergeError::IO(error)
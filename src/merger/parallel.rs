@@ -0,0 +1,78 @@
+use std::cmp;
+use std::sync:: { Mutex };
+use std::thread;
+
+/// Number of worker threads used when no explicit job count is given.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Apply the function to every item on up to `jobs` threads.
+/// The results are returned in the order of the items.
+pub fn map<T, U, F>(items: Vec<T>, jobs: usize, f: F) -> Vec<U>
+    where T: Send, U: Send, F: Fn(T) -> U + Sync {
+    let len = items.len();
+    let workers = cmp::min(cmp::max(jobs, 1), len);
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(len));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some((index, item)) => {
+                        let result = f(item);
+                        results.lock().unwrap().push((index, result));
+                    },
+                    None => break
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Combine the items pairwise, keeping the order of the inputs:
+/// `[a, b, c, d]` is reduced to `(a + b) + (c + d)`.
+pub fn reduce<T, E, F>(mut items: Vec<T>, jobs: usize, combine: F) -> Result<Option<T>, E>
+    where T: Send, E: Send, F: Fn(T, T) -> Result<T, E> + Sync {
+    while items.len() > 1 {
+        let mut pairs = Vec::with_capacity(items.len() / 2 + 1);
+        let mut iter = items.into_iter();
+        while let Some(left) = iter.next() {
+            pairs.push((left, iter.next()));
+        }
+        let combined = map(pairs, jobs, |(left, right)| {
+            match right {
+                Some(right) => combine(left, right),
+                None => Ok(left)
+            }
+        });
+        items = try!(combined.into_iter().collect());
+    }
+    Ok(items.pop())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use merger::parallel:: { map, reduce };
+
+    #[test]
+    fn map_keeps_order() {
+        let items: Vec<u32> = (0..100).collect();
+        let results = map(items, 4, |item| item * 2);
+        assert_eq!(results, (0..100).map(|item| item * 2).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn reduce_keeps_order() {
+        let items: Vec<String> = (0..7).map(|item| item.to_string()).collect();
+        let result: Result<_, ()> = reduce(items, 4, |left, right| Ok(left + &right));
+        assert_eq!(result, Ok(Some("0123456".to_string())));
+    }
+}
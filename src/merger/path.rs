@@ -0,0 +1,131 @@
+use std::path:: { Path, PathBuf, Component };
+use regex:: { Regex, Error as RegexError };
+use report::attribute:: { SourceFile };
+
+/// Rewrites the paths of the source files, so the same file merges under one key
+///
+/// The rules are applied in order: substitutions, prefix stripping, resolution of
+/// relative paths against the base directory, and finally `.` and `..` are collapsed.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::PathNormalizer;
+///
+/// let mut normalizer = PathNormalizer::new();
+/// normalizer
+///     .strip_prefix("/builds/xyz/")
+///     .base_dir("/src");
+///
+/// assert_eq!(normalizer.normalize("/builds/xyz/lib/../main.c"), "/src/main.c");
+/// assert_eq!(normalizer.normalize("./main.c"), "/src/main.c");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PathNormalizer {
+    substitutions: Vec<(Regex, String)>,
+    prefixes: Vec<String>,
+    base_dir: Option<PathBuf>
+}
+
+impl PathNormalizer {
+    pub fn new() -> Self {
+        PathNormalizer {
+            substitutions: vec![],
+            prefixes: vec![],
+            base_dir: None
+        }
+    }
+    /// Replace every match of the pattern, like `lcov --substitute`.
+    ///
+    /// The replacement can refer to the groups of the pattern as `$1` or `${name}`.
+    pub fn substitute(&mut self, pattern: &str, replacement: &str) -> Result<&mut Self, RegexError> {
+        let regex = try!(Regex::new(pattern));
+        self.substitutions.push((regex, replacement.to_string()));
+        Ok(self)
+    }
+    /// Remove the prefix from the paths that start with it.
+    ///
+    /// Only the first matching prefix is stripped.
+    pub fn strip_prefix<T: Into<String>>(&mut self, prefix: T) -> &mut Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+    /// Resolve relative paths against the directory.
+    pub fn base_dir<T: AsRef<Path>>(&mut self, base_dir: T) -> &mut Self {
+        self.base_dir = Some(base_dir.as_ref().to_path_buf());
+        self
+    }
+    pub fn normalize(&self, source_file: &str) -> SourceFile {
+        let mut path = source_file.to_string();
+        for (regex, replacement) in self.substitutions.iter() {
+            path = regex.replace_all(&path, replacement.as_str()).into_owned();
+        }
+        for prefix in self.prefixes.iter() {
+            if path.starts_with(prefix.as_str()) {
+                path = path[prefix.len()..].to_string();
+                break;
+            }
+        }
+        let path = match self.base_dir {
+            Some(ref base_dir) if Path::new(&path).is_relative() => base_dir.join(&path),
+            _ => PathBuf::from(path)
+        };
+        collapse(&path).to_string_lossy().into_owned()
+    }
+}
+
+/// Remove the `.` components and resolve `..` without touching the file system.
+///
+/// A `..` that goes above a relative path is kept, and a `..` above the root is dropped.
+fn collapse(path: &Path) -> PathBuf {
+    let mut components: Vec<Component> = vec![];
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                match components.last() {
+                    Some(&Component::Normal(_)) => { components.pop(); },
+                    Some(&Component::RootDir) | Some(&Component::Prefix(_)) => {},
+                    _ => components.push(component)
+                }
+            },
+            _ => components.push(component)
+        }
+    }
+    components.iter().collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use merger::path:: { PathNormalizer };
+
+    #[test]
+    fn collapse_parent_dirs() {
+        let normalizer = PathNormalizer::new();
+        assert_eq!(normalizer.normalize("/src/lib/../main.c"), "/src/main.c");
+        assert_eq!(normalizer.normalize("/../src/./main.c"), "/src/main.c");
+        assert_eq!(normalizer.normalize("../src/main.c"), "../src/main.c");
+    }
+
+    #[test]
+    fn substitute() {
+        let mut normalizer = PathNormalizer::new();
+        normalizer.substitute("^/home/[^/]+/shared", "/src").unwrap();
+        assert_eq!(normalizer.normalize("/home/vagrant/shared/main.c"), "/src/main.c");
+        assert!(PathNormalizer::new().substitute("(", "").is_err());
+    }
+
+    #[test]
+    fn strip_prefix_and_base_dir() {
+        let mut normalizer = PathNormalizer::new();
+        normalizer
+            .strip_prefix("/builds/xyz/")
+            .strip_prefix("/home/vagrant/shared/")
+            .base_dir("/src");
+        assert_eq!(normalizer.normalize("/builds/xyz/main.c"), "/src/main.c");
+        assert_eq!(normalizer.normalize("/home/vagrant/shared/main.c"), "/src/main.c");
+        assert_eq!(normalizer.normalize("main.c"), "/src/main.c");
+        assert_eq!(normalizer.normalize("/usr/include/stdio.h"), "/usr/include/stdio.h");
+    }
+}
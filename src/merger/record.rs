@@ -0,0 +1,157 @@
+use lcov_parser:: { LCOVRecord, RecordParseError, parse_record };
+use report::branch:: { Branch };
+use report::function:: { Function };
+use report::attribute:: { ExecutionCount, LineNumber };
+
+/// Record of the tracefile, including the syntax that lcov-parser does not keep
+#[derive(Debug, PartialEq, Clone)]
+pub enum Record {
+    Lcov(LCOVRecord),
+    Branch(Branch),                   // BRDA:<line number>,<block number>,<branch number>,<taken or ->
+    Function(Function),               // FN:<line number>,<end line number>,<function name>
+    FunctionLocation(u32, LineNumber, Option<LineNumber>), // FNL:<index>,<line number>[,<end line number>]
+    FunctionAlias(u32, ExecutionCount, String)              // FNA:<index>,<execution count>,<function name>
+}
+
+/// Parse the record of the line.
+///
+/// A bare `TN` line is read as an empty test name, and `BRDA` keeps the `-` of a branch never evaluated.
+/// The function records of LCOV 2.x (`FN` with an end line, `FNL` and `FNA`) are read as well.
+pub fn read_record(line: &str) -> Result<Record, RecordParseError> {
+    let content = line.trim_end();
+    if content == "TN" {
+        return Ok(Record::Lcov(LCOVRecord::TestName(None)));
+    }
+    if let Some(fields) = content.strip_prefix("FNL:") {
+        return read_function_location(fields);
+    }
+    if let Some(fields) = content.strip_prefix("FNA:") {
+        return read_function_alias(fields);
+    }
+    if let Some(fields) = content.strip_prefix("FN:") {
+        let fields: Vec<&str> = fields.splitn(3, ',').collect();
+        if fields.len() == 3 && is_number(fields[1]) {
+            let line_number = try!(read_number(fields[0], 4));
+            let end_line_number = try!(read_number(fields[1], 5 + fields[0].len()));
+            let function = Function::new(fields[2].to_string(), line_number, 0)
+                .with_end_line_number(Some(end_line_number));
+            return Ok(Record::Function(function));
+        }
+    }
+    let record = try!(parse_record(line));
+
+    match record {
+        LCOVRecord::BranchData(ref data) => {
+            let branch = if line.trim_end().ends_with(",-") {
+                Branch::not_evaluated(data.line, data.block, data.branch)
+            } else {
+                Branch::from(data)
+            };
+            Ok(Record::Branch(branch))
+        },
+        _ => Ok(Record::Lcov(record))
+    }
+}
+
+fn read_function_location(fields: &str) -> Result<Record, RecordParseError> {
+    let values: Vec<&str> = fields.split(',').collect();
+    if values.len() < 2 || values.len() > 3 {
+        return Err(parse_error(5, "Expected `<index>,<line number>[,<end line number>]`"));
+    }
+    let index = try!(read_number(values[0], 5));
+    let line_number = try!(read_number(values[1], 6 + values[0].len()));
+    let end_line_number = match values.get(2) {
+        Some(value) => Some(try!(read_number(value, 7 + values[0].len() + values[1].len()))),
+        None => None
+    };
+    Ok(Record::FunctionLocation(index, line_number, end_line_number))
+}
+
+fn read_function_alias(fields: &str) -> Result<Record, RecordParseError> {
+    let values: Vec<&str> = fields.splitn(3, ',').collect();
+    if values.len() != 3 || values[2].is_empty() {
+        return Err(parse_error(5, "Expected `<index>,<execution count>,<function name>`"));
+    }
+    let index = try!(read_number(values[0], 5));
+    let execution_count = try!(read_number(values[1], 6 + values[0].len()));
+    Ok(Record::FunctionAlias(index, execution_count, values[2].to_string()))
+}
+
+fn is_number(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+fn read_number<T: ::std::str::FromStr>(value: &str, column: usize) -> Result<T, RecordParseError> {
+    if !is_number(value) {
+        return Err(parse_error(column, format!("Expected a number, found `{}`", value)));
+    }
+    value.parse().map_err(|_| parse_error(column, format!("Number out of range `{}`", value)))
+}
+
+/// Build the error in the same shape as the errors of lcov-parser.
+fn parse_error<T: Into<String>>(column: usize, details: T) -> RecordParseError {
+    RecordParseError {
+        line: 1,
+        column: column as u32,
+        message: format!("Parse error at 1:{}\n{}\n", column, details.into())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use lcov_parser:: { LCOVRecord };
+    use merger::record:: { Record, read_record };
+    use report::function:: { Function };
+
+    #[test]
+    fn bare_test_name() {
+        assert_eq!(read_record("TN\n"), Ok(Record::Lcov(LCOVRecord::TestName(None))));
+    }
+
+    #[test]
+    fn branch_data() {
+        let taken = read_record("BRDA:8,0,1,2\n").unwrap();
+        let not_taken = read_record("BRDA:8,0,1,0\n").unwrap();
+        let not_evaluated = read_record("BRDA:8,0,1,-\n").unwrap();
+
+        match (taken, not_taken, not_evaluated) {
+            (Record::Branch(taken), Record::Branch(not_taken), Record::Branch(not_evaluated)) => {
+                assert_eq!(taken.execution_count(), Some(&2));
+                assert_eq!(not_taken.execution_count(), Some(&0));
+                assert_eq!(not_evaluated.execution_count(), None);
+                assert_eq!((*taken.line_number(), *taken.block(), *taken.branch()), (8, 0, 1));
+            },
+            _ => panic!("expected branch records")
+        }
+    }
+
+    #[test]
+    fn function_with_end_line() {
+        match read_record("FN:4,10,main\n").unwrap() {
+            Record::Function(function) => {
+                assert_eq!(function.name(), "main");
+                assert_eq!(function.line_number(), &4);
+                assert_eq!(function.end_line_number(), Some(&10));
+            },
+            _ => panic!("expected a function record")
+        }
+        match read_record("FN:4,main\n").unwrap() {
+            Record::Lcov(LCOVRecord::FunctionName(name)) => assert_eq!(name.name, "main"),
+            _ => panic!("expected a legacy function record")
+        }
+        assert!(Function::new("main".to_string(), 4, 0).end_line_number().is_none());
+    }
+
+    #[test]
+    fn function_location_and_alias() {
+        assert_eq!(read_record("FNL:0,4,10\n"), Ok(Record::FunctionLocation(0, 4, Some(10))));
+        assert_eq!(read_record("FNL:1,12\n"), Ok(Record::FunctionLocation(1, 12, None)));
+        assert_eq!(read_record("FNA:0,3,main\n"), Ok(Record::FunctionAlias(0, 3, "main".to_string())));
+        assert_eq!(read_record("FNA:0,3,operator,\n"), Ok(Record::FunctionAlias(0, 3, "operator,".to_string())));
+
+        let error = read_record("FNL:a,4\n").unwrap_err();
+        assert_eq!(error.column, 5);
+        assert!(read_record("FNA:0,3\n").is_err());
+    }
+}
@@ -12,3 +12,8 @@ impl Hit for ExecutionCount {
         *self > 0
     }
 }
+
+/// Execution count of a 64 bits counter of gcov or LLVM, saturated to `ExecutionCount`
+pub fn execution_count(count: u64) -> ExecutionCount {
+    ::std::cmp::min(count, ExecutionCount::MAX as u64) as ExecutionCount
+}
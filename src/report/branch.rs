@@ -4,7 +4,7 @@ use std::fmt:: { Display, Formatter, Result };
 use std::collections::btree_map:: { BTreeMap };
 use std::convert::{ From };
 use lcov_parser:: { BranchData };
-use merger::ops:: { TryMerge, MergeResult, MergeBranch, BranchError };
+use merger::ops:: { Merge, TryMerge, MergeResult, MergeBranch, BranchError };
 use record:: { RecordWrite };
 use report::summary:: { Summary };
 use report::attribute:: { LineNumber, ExecutionCount };
@@ -48,12 +48,27 @@ impl Display for BranchUnit {
     }
 }
 
+/// Branch of the line
+///
+/// The execution count is `None` when the line was never executed,
+/// which LCOV writes as `-` (`BRDA:<line>,<block>,<branch>,-`).
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::branch::Branch;
+///
+/// let branch = Branch::not_evaluated(1, 0, 0);
+///
+/// assert_eq!(branch.execution_count(), None);
+/// assert!(!branch.is_evaluated());
+/// ```
 #[derive(Debug, Clone)]
 pub struct Branch {
     line_number: LineNumber,
     block: u32,
     branch: u32,
-    execution_count: ExecutionCount
+    execution_count: Option<ExecutionCount>
 }
 
 impl Branch {
@@ -67,7 +82,19 @@ impl Branch {
             line_number: line_number,
             block: block,
             branch: branch,
-            execution_count: execution_count
+            execution_count: Some(execution_count)
+        }
+    }
+    pub fn not_evaluated(
+        line_number: LineNumber,
+        block: u32,
+        branch: u32
+    ) -> Self {
+        Branch {
+            line_number: line_number,
+            block: block,
+            branch: branch,
+            execution_count: None
         }
     }
     pub fn line_number(&self) -> &LineNumber {
@@ -79,8 +106,18 @@ impl Branch {
     pub fn branch(&self) -> &u32 {
         &self.branch
     }
-    pub fn execution_count(&self) -> &ExecutionCount {
-        &self.execution_count
+    pub fn execution_count(&self) -> Option<&ExecutionCount> {
+        self.execution_count.as_ref()
+    }
+    pub fn is_evaluated(&self) -> bool {
+        self.execution_count.is_some()
+    }
+    fn add_execution_count(&mut self, execution_count: Option<&ExecutionCount>) {
+        self.execution_count = match (self.execution_count, execution_count) {
+            (Some(count), Some(other_count)) => Some(count.saturating_add(*other_count)),
+            (None, Some(other_count)) => Some(*other_count),
+            (count, None) => count
+        };
     }
 }
 
@@ -141,7 +178,7 @@ impl<'a> TryMerge<&'a BranchData> for Branch {
                 )
             );
         }
-        self.execution_count += data.taken;
+        self.add_execution_count(Some(&data.taken));
         Ok(())
     }
 }
@@ -158,14 +195,17 @@ impl<'a> TryMerge<&'a Branch> for Branch {
                 )
             );
         }
-        self.execution_count += *other.execution_count();
+        self.add_execution_count(other.execution_count());
         Ok(())
     }
 }
 
 impl Hit for Branch {
     fn is_hit(&self) -> bool {
-        self.execution_count.is_hit()
+        match self.execution_count {
+            Some(ref execution_count) => execution_count.is_hit(),
+            None => false
+        }
     }
 }
 
@@ -186,6 +226,7 @@ impl BranchBlocks {
 }
 
 impl_summary!(BranchBlocks, blocks<BranchUnit, Branch>);
+impl_summary_mut!(BranchBlocks, blocks<BranchUnit, Branch>);
 
 
 impl HitCounter for BranchBlocks {
@@ -220,6 +261,20 @@ impl<'a> TryMerge<&'a BranchData> for BranchBlocks {
     }
 }
 
+impl<'a> TryMerge<&'a Branch> for BranchBlocks {
+    type Err = BranchError;
+
+    fn try_merge(&mut self, branch: &'a Branch) -> MergeResult<Self::Err> {
+        let unit = BranchUnit::new(branch.block, branch.branch);
+        if !self.blocks.contains_key(&unit) {
+            self.blocks.insert(unit, branch.clone());
+            return Ok(());
+        }
+        let block = self.blocks.get_mut(&unit).unwrap();
+        block.try_merge(branch)
+    }
+}
+
 impl_try_merge_self_summary!(BranchBlocks:blocks, BranchError);
 
 
@@ -257,6 +312,7 @@ impl HitFoundCounter for Branches {}
 
 
 impl_summary!(Branches, branches<LineNumber, BranchBlocks>);
+impl_summary_mut!(Branches, branches<LineNumber, BranchBlocks>);
 
 
 impl RecordWrite for Branches {
@@ -272,8 +328,12 @@ impl Display for Branches {
         }
         for (line_number, blocks) in self.iter() {
             for (_, branch) in blocks.iter() {
-                try!(writeln!(f, "BRDA:{},{},{},{}",
-                    line_number, branch.block(), branch.branch(), branch.execution_count()));
+                match branch.execution_count() {
+                    Some(execution_count) => try!(writeln!(f, "BRDA:{},{},{},{}",
+                        line_number, branch.block(), branch.branch(), execution_count)),
+                    None => try!(writeln!(f, "BRDA:{},{},{},-",
+                        line_number, branch.block(), branch.branch()))
+                }
             }
         }
         try!(writeln!(f, "BRF:{}", self.found_count()));
@@ -307,6 +367,35 @@ impl<'a> TryMerge<&'a BranchData> for Branches {
     }
 }
 
+impl<'a> TryMerge<&'a Branch> for Branches {
+    type Err = BranchError;
+
+    fn try_merge(&mut self, branch: &'a Branch) -> MergeResult<Self::Err> {
+        if self.branches.contains_key(&branch.line_number) {
+            let blocks = self.branches.get_mut(&branch.line_number).unwrap();
+            blocks.try_merge(branch)
+        } else {
+            let blocks = {
+                let mut blocks = BranchBlocks::new();
+                try!(blocks.try_merge(branch));
+                blocks
+            };
+            self.branches.insert(branch.line_number, blocks);
+            Ok(())
+        }
+    }
+}
+
+/// Sum up the execution counts by line, block and branch.
+impl<'a> Merge<&'a Branch> for Branches {
+    fn merge(&mut self, branch: &'a Branch) {
+        let blocks = self.branches.entry(branch.line_number).or_insert_with(BranchBlocks::new);
+        blocks.blocks.entry(BranchUnit::new(branch.block, branch.branch))
+            .or_insert_with(|| Branch::not_evaluated(branch.line_number, branch.block, branch.branch))
+            .add_execution_count(branch.execution_count());
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -380,6 +469,35 @@ mod tests {
         assert_eq!(branches.found_count(), 2);
     }
 
+    #[test]
+    fn merge_not_evaluated_branch() {
+        let mut branches = BranchBlocks::new();
+        branches.try_merge(&Branch::not_evaluated(1, 0, 0)).unwrap();
+        branches.try_merge(&Branch::not_evaluated(1, 0, 0)).unwrap();
+        branches.try_merge(&Branch::not_evaluated(1, 0, 1)).unwrap();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 1, taken: 0 }).unwrap();
+        branches.try_merge(&Branch::not_evaluated(1, 0, 2)).unwrap();
+        branches.try_merge(&Branch::new(1, 0, 2, 3)).unwrap();
+
+        let execution_count = |branch: u32| {
+            branches.get(&BranchUnit::new(0, branch)).unwrap().execution_count().cloned()
+        };
+        assert_eq!(execution_count(0), None);
+        assert_eq!(execution_count(1), Some(0));
+        assert_eq!(execution_count(2), Some(3));
+        assert_eq!(branches.hit_count(), 1);
+        assert_eq!(branches.found_count(), 3);
+    }
+
+    #[test]
+    fn display_not_evaluated_branch() {
+        let mut branches = Branches::new();
+        branches.try_merge(&Branch::not_evaluated(1, 0, 0)).unwrap();
+        branches.try_merge(&Branch::new(1, 0, 1, 2)).unwrap();
+
+        assert_eq!(branches.to_string(), "BRDA:1,0,0,-\nBRDA:1,0,1,2\nBRF:2\nBRH:1\n");
+    }
+
     #[test]
     fn branches_hit_count_and_found_count() {
         let mut branches = Branches::new();
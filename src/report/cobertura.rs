@@ -0,0 +1,490 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::fmt:: { Display, Formatter, Result };
+use std::io:: { Read };
+use std::path:: { Path };
+use std::str:: { FromStr };
+use std::time:: { SystemTime, UNIX_EPOCH };
+use report:: { Report };
+use report::file:: { File };
+use report::summary:: { Summary };
+use report::coverage:: { Coverage, CoverageSummary };
+use report::counter:: { HitCounter, FoundCounter };
+use report::xml:: { escape };
+use report::branch:: { Branch };
+use report::function:: { Function };
+use report::attribute:: { LineNumber, ExecutionCount, execution_count };
+use lcov_parser:: { LineData };
+use xml::attribute:: { OwnedAttribute };
+use xml::common:: { Position };
+use xml::reader:: { EventReader, XmlEvent };
+
+/// The report as Cobertura XML, for GitLab and Jenkins
+///
+/// Every directory becomes a package, and every source file a class of its package.
+/// The tests of a source file are summed up.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::merge_files;
+///
+/// let report = merge_files(&["tests/fixtures/fixture1.info"]).unwrap();
+/// let xml = report.cobertura()
+///     .source_dir("/home/vagrant/shared/lcov-merge")
+///     .timestamp(0)
+///     .to_string();
+///
+/// assert!(xml.contains(r#"<package name="tests.fixtures""#));
+/// assert!(xml.contains(r#"<class name="func1.c" filename="tests/fixtures/func1.c""#));
+/// ```
+pub struct Cobertura<'a> {
+    report: &'a Report,
+    source_dir: Option<String>,
+    timestamp: u64
+}
+
+impl<'a> Cobertura<'a> {
+    pub fn new(report: &'a Report) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() * 1000 + elapsed.subsec_millis() as u64)
+            .unwrap_or(0);
+        Cobertura {
+            report: report,
+            source_dir: None,
+            timestamp: timestamp
+        }
+    }
+    /// Write the paths of the source files under the directory relative to it,
+    /// and the directory as the source of the report.
+    pub fn source_dir<T: Into<String>>(mut self, source_dir: T) -> Self {
+        self.source_dir = Some(source_dir.into());
+        self
+    }
+    /// Milliseconds since the epoch written as the time of the report, the current time by default
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+    fn filename(&self, source_file: &str) -> String {
+        let source_dir = match self.source_dir {
+            Some(ref source_dir) => source_dir,
+            None => return source_file.to_string()
+        };
+        match Path::new(source_file).strip_prefix(source_dir) {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => source_file.to_string()
+        }
+    }
+    /// Source files grouped by the package of their directory
+    fn packages(&self) -> BTreeMap<String, Vec<(String, &'a File)>> {
+        let mut packages = BTreeMap::new();
+        for (source_file, file) in self.report.files().iter() {
+            let filename = self.filename(source_file);
+            let package = Path::new(&filename).parent()
+                .map(|parent| parent.to_string_lossy().replace('/', "."))
+                .unwrap_or_default();
+            let package = package.trim_start_matches('.').to_string();
+            packages.entry(package).or_insert_with(Vec::new).push((filename, file));
+        }
+        packages
+    }
+}
+
+/// Ratio of the hit items, which is 1 when nothing was found
+fn rate(coverage: &Coverage) -> String {
+    let rate = match coverage.percentage() {
+        Some(percentage) => percentage / 100.0,
+        None => 1.0
+    };
+    let rate = format!("{:.4}", rate);
+    rate.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+impl<'a> Display for Cobertura<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let summary = self.report.coverage();
+        try!(writeln!(f, r#"<?xml version="1.0" ?>"#));
+        try!(writeln!(f, r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#));
+        try!(writeln!(f,
+            r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="{} {}" timestamp="{}">"#,
+            rate(&summary.lines), rate(&summary.branches),
+            summary.lines.hit, summary.lines.found, summary.branches.hit, summary.branches.found,
+            env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), self.timestamp));
+
+        match self.source_dir {
+            Some(ref source_dir) => {
+                try!(writeln!(f, "  <sources>"));
+                try!(writeln!(f, "    <source>{}</source>", escape(source_dir)));
+                try!(writeln!(f, "  </sources>"));
+            },
+            None => try!(writeln!(f, "  <sources/>"))
+        }
+
+        try!(writeln!(f, "  <packages>"));
+        for (package, files) in self.packages().iter() {
+            let mut package_summary = CoverageSummary::new();
+            for &(_, file) in files.iter() {
+                package_summary += file.coverage();
+            }
+            try!(writeln!(f, r#"    <package name="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+                escape(package), rate(&package_summary.lines), rate(&package_summary.branches)));
+            try!(writeln!(f, "      <classes>"));
+            for &(ref filename, file) in files.iter() {
+                try!(write_class(f, filename, file));
+            }
+            try!(writeln!(f, "      </classes>"));
+            try!(writeln!(f, "    </package>"));
+        }
+        try!(writeln!(f, "  </packages>"));
+        writeln!(f, "</coverage>")
+    }
+}
+
+fn write_class(f: &mut Formatter, filename: &str, file: &File) -> Result {
+    let test = file.flatten();
+    let summary = CoverageSummary::of_test(&test);
+    let name = Path::new(filename).file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| filename.to_string());
+
+    try!(writeln!(f, r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+        escape(&name), escape(filename), rate(&summary.lines), rate(&summary.branches)));
+
+    try!(writeln!(f, "          <methods>"));
+    for (name, function) in test.functions().iter() {
+        let line_rate = if function.is_hit() { "1" } else { "0" };
+        try!(writeln!(f, r#"            <method name="{}" signature="" line-rate="{}" branch-rate="1" complexity="0">"#,
+            escape(name), line_rate));
+        try!(writeln!(f, r#"              <lines><line number="{}" hits="{}"/></lines>"#,
+            function.line_number(), function.execution_count()));
+        try!(writeln!(f, "            </method>"));
+    }
+    try!(writeln!(f, "          </methods>"));
+
+    try!(writeln!(f, "          <lines>"));
+    for line_number in test.line_numbers().iter() {
+        let blocks = test.branches().get(line_number).filter(|blocks| !blocks.is_empty());
+        // A line with only branches was executed when one of its branches was taken.
+        let hits = match (test.lines().get(line_number), blocks) {
+            (Some(line), _) => *line.execution_count(),
+            (None, Some(blocks)) => if blocks.hit_count() > 0 { 1 } else { 0 },
+            (None, None) => continue
+        };
+        match blocks {
+            Some(blocks) => {
+                let (hit, found) = (blocks.hit_count(), blocks.found_count());
+                try!(writeln!(f, r#"            <line number="{}" hits="{}" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+                    line_number, hits, hit * 100 / found, hit, found));
+            },
+            None => {
+                try!(writeln!(f, r#"            <line number="{}" hits="{}" branch="false"/>"#, line_number, hits));
+            }
+        }
+    }
+    try!(writeln!(f, "          </lines>"));
+    writeln!(f, "        </class>")
+}
+
+
+/// Error of a Cobertura report that can't be read
+#[derive(Debug, PartialEq, Clone)]
+pub struct CoberturaError {
+    pub line: u32,
+    pub message: String
+}
+
+impl CoberturaError {
+    fn new<P: Position, T: Into<String>>(position: &P, message: T) -> Self {
+        CoberturaError {
+            line: position.position().row as u32 + 1,
+            message: message.into()
+        }
+    }
+}
+
+impl Display for CoberturaError {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Coverage of a `<class>` of a Cobertura report
+///
+/// The lines with a `condition-coverage` get synthetic branches of the block 0,
+/// the taken ones first, as Cobertura doesn't tell which branches were taken.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CoberturaClass {
+    pub filename: String,
+    pub lines: Vec<LineData>,
+    pub functions: Vec<Function>,
+    pub branches: Vec<Branch>
+}
+
+/// The contents of a Cobertura report read as a merge input
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::cobertura:: { CoberturaReport };
+///
+/// let xml = r#"<coverage><sources><source>/src</source></sources><packages><package name=""><classes>
+///   <class name="main.c" filename="main.c"><lines><line number="1" hits="2"/></lines></class>
+/// </classes></package></packages></coverage>"#;
+/// let report = CoberturaReport::from_reader(xml.as_bytes()).unwrap();
+///
+/// assert_eq!(report.source_file(&report.classes[0]), "/src/main.c");
+/// assert_eq!(report.classes[0].lines[0].count, 2);
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CoberturaReport {
+    pub sources: Vec<String>,
+    pub classes: Vec<CoberturaClass>
+}
+
+impl CoberturaReport {
+    pub fn from_reader<R: Read>(reader: R) -> ::std::result::Result<Self, CoberturaError> {
+        let mut parser = EventReader::new(reader);
+        let mut report = CoberturaReport::default();
+        let mut source: Option<String> = None;
+        let mut class: Option<CoberturaClass> = None;
+        let mut method: Option<(String, Option<(LineNumber, ExecutionCount)>)> = None;
+
+        loop {
+            let event = try!(parser.next().map_err(|err| CoberturaError::new(&err, err.msg())));
+            match event {
+                XmlEvent::StartElement { ref name, ref attributes, .. } => match name.local_name.as_str() {
+                    "source" => source = Some(String::new()),
+                    "class" => {
+                        let filename = try!(required(&parser, attributes, "filename"));
+                        class = Some(CoberturaClass { filename: filename.to_string(), ..Default::default() });
+                    },
+                    "method" => {
+                        let name = try!(required(&parser, attributes, "name"));
+                        method = Some((name.to_string(), None));
+                    },
+                    "line" => {
+                        let line_number: LineNumber = try!(number(&parser, attributes, "number"));
+                        let hits = execution_count(try!(number(&parser, attributes, "hits")));
+                        if let Some((_, ref mut location)) = method {
+                            location.get_or_insert((line_number, hits));
+                        } else if let Some(ref mut class) = class {
+                            class.lines.push(LineData { line: line_number, count: hits, checksum: None });
+                            if let Some(coverage) = attribute(attributes, "condition-coverage") {
+                                let (taken, total) = try!(condition_coverage(coverage).ok_or_else(|| {
+                                    CoberturaError::new(&parser, format!("invalid condition-coverage '{}'", coverage))
+                                }));
+                                class.branches.extend((0..total).map(|branch| match hits {
+                                    0 => Branch::not_evaluated(line_number, 0, branch),
+                                    _ => Branch::new(line_number, 0, branch, if branch < taken { 1 } else { 0 })
+                                }));
+                            }
+                        }
+                    },
+                    _ => {}
+                },
+                XmlEvent::Characters(ref text) => {
+                    if let Some(ref mut source) = source {
+                        source.push_str(text);
+                    }
+                },
+                XmlEvent::EndElement { ref name } => match name.local_name.as_str() {
+                    "source" => {
+                        if let Some(source) = source.take() {
+                            report.sources.push(source.trim().to_string());
+                        }
+                    },
+                    "method" => {
+                        if let (Some((name, location)), Some(ref mut class)) = (method.take(), class.as_mut()) {
+                            let (line_number, hits) = location.unwrap_or((0, 0));
+                            class.functions.push(Function::new(name, line_number, hits));
+                        }
+                    },
+                    "class" => {
+                        if let Some(class) = class.take() {
+                            report.classes.push(class);
+                        }
+                    },
+                    _ => {}
+                },
+                XmlEvent::EndDocument => break,
+                _ => {}
+            }
+        }
+        Ok(report)
+    }
+    /// Path of the source file of the class
+    ///
+    /// A relative filename is joined to the first `<source>`, whatever the files of the merging host,
+    /// so the paths are the same wherever the report is merged. `PathNormalizer` remaps them.
+    pub fn source_file(&self, class: &CoberturaClass) -> String {
+        if Path::new(&class.filename).is_absolute() {
+            return class.filename.clone();
+        }
+        match self.sources.first().map(|source| source.as_str()) {
+            None | Some("") | Some(".") => class.filename.clone(),
+            Some(source) => Path::new(source).join(&class.filename).to_string_lossy().into_owned()
+        }
+    }
+}
+
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes.iter()
+        .find(|attribute| attribute.name.local_name == name)
+        .map(|attribute| attribute.value.as_str())
+}
+
+fn required<'a, P: Position>(position: &P, attributes: &'a [OwnedAttribute], name: &str) -> ::std::result::Result<&'a str, CoberturaError> {
+    attribute(attributes, name).ok_or_else(|| CoberturaError::new(position, format!("missing attribute '{}'", name)))
+}
+
+fn number<P: Position, T: FromStr>(position: &P, attributes: &[OwnedAttribute], name: &str) -> ::std::result::Result<T, CoberturaError> {
+    let value = try!(required(position, attributes, name));
+    value.parse().map_err(|_| CoberturaError::new(position, format!("attribute '{}' expects a number, got '{}'", name, value)))
+}
+
+/// Taken and total branches of `condition-coverage="50% (1/2)"`
+fn condition_coverage(value: &str) -> Option<(u32, u32)> {
+    let start = value.find('(')?;
+    let end = value.rfind(')')?;
+    let mut parts = value.get(start + 1..end)?.splitn(2, '/');
+    let taken = parts.next()?.trim().parse().ok()?;
+    let total = parts.next()?.trim().parse().ok()?;
+    Some((taken, total))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use merger::*;
+    use report::summary:: { Summary };
+    use report::cobertura:: { CoberturaReport, CoberturaError };
+
+    #[test]
+    fn cobertura() {
+        let input = concat!(
+            "TN:\nSF:/src/lib/a&b.c\nFN:1,main\nFNDA:2,main\n",
+            "BRDA:2,0,0,1\nBRDA:2,0,1,0\nDA:1,2\nDA:2,2\nDA:3,0\nend_of_record\n",
+            "TN:\nSF:/src/main.c\nDA:1,1\nend_of_record\n"
+        );
+        let report = merge_readers(vec![Cursor::new(input.as_bytes())]).unwrap();
+
+        assert_eq!(report.cobertura().source_dir("/src").timestamp(1).to_string(), r#"<?xml version="1.0" ?>
+<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">
+<coverage line-rate="0.75" branch-rate="0.5" lines-covered="3" lines-valid="4" branches-covered="1" branches-valid="2" complexity="0" version="lcov-merge 0.0.1" timestamp="1">
+  <sources>
+    <source>/src</source>
+  </sources>
+  <packages>
+    <package name="" line-rate="1" branch-rate="1" complexity="0">
+      <classes>
+        <class name="main.c" filename="main.c" line-rate="1" branch-rate="1" complexity="0">
+          <methods>
+          </methods>
+          <lines>
+            <line number="1" hits="1" branch="false"/>
+          </lines>
+        </class>
+      </classes>
+    </package>
+    <package name="lib" line-rate="0.6667" branch-rate="0.5" complexity="0">
+      <classes>
+        <class name="a&amp;b.c" filename="lib/a&amp;b.c" line-rate="0.6667" branch-rate="0.5" complexity="0">
+          <methods>
+            <method name="main" signature="" line-rate="1" branch-rate="1" complexity="0">
+              <lines><line number="1" hits="2"/></lines>
+            </method>
+          </methods>
+          <lines>
+            <line number="1" hits="2" branch="false"/>
+            <line number="2" hits="2" branch="true" condition-coverage="50% (1/2)"/>
+            <line number="3" hits="0" branch="false"/>
+          </lines>
+        </class>
+      </classes>
+    </package>
+  </packages>
+</coverage>
+"#);
+    }
+
+    #[test]
+    fn branch_only_lines() {
+        let input = "TN:\nSF:/src/main.c\nBRDA:2,0,0,1\nBRDA:2,0,1,0\nBRDA:3,0,0,-\nBRDA:3,0,1,-\nDA:1,1\nend_of_record\n";
+        let report = merge_readers(vec![Cursor::new(input.as_bytes())]).unwrap();
+        let xml = report.cobertura().to_string();
+
+        assert!(xml.contains(r#"branch-rate="0.25" lines-covered="1" lines-valid="1" branches-covered="1" branches-valid="4""#));
+        assert!(xml.contains(r#"<line number="1" hits="1" branch="false"/>"#));
+        assert!(xml.contains(r#"<line number="2" hits="1" branch="true" condition-coverage="50% (1/2)"/>"#));
+        assert!(xml.contains(r#"<line number="3" hits="0" branch="true" condition-coverage="0% (0/2)"/>"#));
+    }
+
+    #[test]
+    fn sources() {
+        let xml = r#"<coverage><sources><source>/missing</source><source>tests</source></sources><packages><package><classes>
+            <class filename="fixtures/fixture.c"/><class filename="fixtures/missing.c"/><class filename="/src/main.c"/>
+        </classes></package></packages></coverage>"#;
+        let report = CoberturaReport::from_reader(xml.as_bytes()).unwrap();
+
+        assert_eq!(report.source_file(&report.classes[0]), "/missing/fixtures/fixture.c");
+        assert_eq!(report.source_file(&report.classes[1]), "/missing/fixtures/missing.c");
+        assert_eq!(report.source_file(&report.classes[2]), "/src/main.c");
+    }
+
+    #[test]
+    fn clamped_hits() {
+        let xml = r#"<coverage><packages><package><classes><class filename="a.c"><lines>
+            <line number="1" hits="4294967296"/>
+        </lines></class></classes></package></packages></coverage>"#;
+        let report = CoberturaReport::from_reader(xml.as_bytes()).unwrap();
+
+        assert_eq!(report.classes[0].lines[0].count, u32::MAX);
+    }
+
+    #[test]
+    fn read_written_report() {
+        let report = merge_files(&["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let xml = report.cobertura().source_dir("/home/vagrant/shared/lcov-merge").to_string();
+        let cobertura = CoberturaReport::from_reader(xml.as_bytes()).unwrap();
+
+        assert_eq!(cobertura.sources, vec!["/home/vagrant/shared/lcov-merge".to_string()]);
+        assert_eq!(cobertura.classes.len(), report.len());
+
+        let imported = merge_readers(vec![Cursor::new(xml.as_bytes())]).unwrap();
+        for (source_file, file) in report.files().iter() {
+            assert_eq!(imported.get(source_file).unwrap().coverage(), file.coverage());
+        }
+    }
+
+    #[test]
+    fn synthetic_branches() {
+        let xml = r#"<coverage><packages><package><classes><class filename="a.c"><lines>
+            <line number="2" hits="3" branch="true" condition-coverage="33% (1/3)"/>
+            <line number="4" hits="0" branch="true" condition-coverage="0% (0/2)"/>
+        </lines></class></classes></package></packages></coverage>"#;
+        let report = CoberturaReport::from_reader(xml.as_bytes()).unwrap();
+
+        assert_eq!(report.source_file(&report.classes[0]), "a.c");
+        let branches: Vec<(u32, u32, u32, Option<u32>)> = report.classes[0].branches.iter()
+            .map(|branch| (*branch.line_number(), *branch.block(), *branch.branch(), branch.execution_count().cloned()))
+            .collect();
+        assert_eq!(branches, vec![
+            (2, 0, 0, Some(1)),
+            (2, 0, 1, Some(0)),
+            (2, 0, 2, Some(0)),
+            (4, 0, 0, None),
+            (4, 0, 1, None)
+        ]);
+    }
+
+    #[test]
+    fn invalid_condition_coverage() {
+        let xml = "<coverage>\n<class filename=\"a.c\"><line number=\"1\" hits=\"1\" condition-coverage=\"50%\"/></class></coverage>";
+
+        assert_eq!(CoberturaReport::from_reader(xml.as_bytes()), Err(CoberturaError {
+            line: 2,
+            message: "invalid condition-coverage '50%'".to_string()
+        }));
+    }
+}
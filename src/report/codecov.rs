@@ -0,0 +1,109 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::fmt:: { Display, Formatter, Result as FormatResult };
+use serde:: { Serialize };
+use report:: { Report };
+use report::summary:: { Summary };
+use report::counter:: { HitCounter, FoundCounter };
+use report::attribute:: { LineNumber, ExecutionCount, SourceFile };
+
+/// The Codecov JSON coverage payload
+///
+/// ```json
+/// { "coverage": { "src/main.c": { "1": 2, "2": "1/2" } } }
+/// ```
+///
+/// A line with branches, including a line with only `BRDA` records, is written as the number
+/// of the taken branches over all of its branches.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct CodecovReport {
+    pub coverage: BTreeMap<SourceFile, BTreeMap<LineNumber, CodecovLine>>
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(untagged)]
+pub enum CodecovLine {
+    Hits(ExecutionCount),
+    Branches(String)
+}
+
+impl<'a> From<&'a Report> for CodecovReport {
+    fn from(report: &'a Report) -> Self {
+        let coverage = report.files().iter().map(|(source_file, file)| {
+            let test = file.flatten();
+            let lines = test.line_numbers().into_iter().filter_map(|line_number| {
+                let coverage = match (test.branches().get(&line_number), test.lines().get(&line_number)) {
+                    (Some(blocks), _) if !blocks.is_empty() => {
+                        CodecovLine::Branches(format!("{}/{}", blocks.hit_count(), blocks.found_count()))
+                    },
+                    (_, Some(line)) => CodecovLine::Hits(*line.execution_count()),
+                    _ => return None
+                };
+                Some((line_number, coverage))
+            }).collect();
+            (source_file.clone(), lines)
+        }).collect();
+
+        CodecovReport {
+            coverage: coverage
+        }
+    }
+}
+
+/// The report as a Codecov JSON payload, to be uploaded by the Codecov uploader
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::merge_files;
+///
+/// let report = merge_files(&["tests/fixtures/fixture1.info"]).unwrap();
+/// let json = report.codecov().to_string();
+///
+/// assert!(json.contains(r#""/home/vagrant/shared/lcov-merge/tests/fixtures/func1.c": {"#));
+/// ```
+pub struct Codecov<'a> {
+    report: &'a Report
+}
+
+impl<'a> Codecov<'a> {
+    pub fn new(report: &'a Report) -> Self {
+        Codecov {
+            report: report
+        }
+    }
+}
+
+impl<'a> Display for Codecov<'a> {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        let json = try!(serde_json::to_string_pretty(&CodecovReport::from(self.report)).map_err(|_| ::std::fmt::Error));
+        writeln!(f, "{}", json)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use merger::*;
+
+    #[test]
+    fn codecov() {
+        let input = concat!(
+            "TN:unit\nSF:/src/main.c\nBRDA:2,0,0,1\nBRDA:2,0,1,-\nDA:1,2\nDA:2,2\nDA:10,0\nend_of_record\n",
+            "TN:integration\nSF:/src/main.c\nBRDA:4,0,0,0\nBRDA:4,0,1,-\nDA:1,1\nend_of_record\n"
+        );
+        let report = merge_readers(vec![Cursor::new(input.as_bytes())]).unwrap();
+
+        assert_eq!(report.codecov().to_string(), r#"{
+  "coverage": {
+    "/src/main.c": {
+      "1": 3,
+      "2": "1/2",
+      "4": "0/2",
+      "10": 0
+    }
+  }
+}
+"#);
+    }
+}
@@ -0,0 +1,61 @@
+use std::str:: { FromStr };
+use std::fmt:: { Display, Formatter, Result as FormatResult };
+use report::attribute:: { SourceFile, TestName, LineNumber, CheckSum };
+
+/// How to resolve two tracefiles that disagree on the checksum of a line
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ConflictPolicy {
+    /// Fail the merge with `ChecksumError::Mismatch`
+    #[default]
+    Error,
+    /// Keep the line that was merged first
+    KeepFirst,
+    /// Replace the merged line with the later one
+    KeepLast,
+    /// Drop the conflicting record (the test of the source file) of the later tracefile
+    DropRecord,
+    /// Drop the line from the merged report
+    DropLine
+}
+
+impl ConflictPolicy {
+    /// Whether the merged report or the recorded conflicts depend on the order the tracefiles are merged in.
+    pub fn is_order_dependent(&self) -> bool {
+        *self != ConflictPolicy::Error
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "error" => Ok(ConflictPolicy::Error),
+            "keep-first" => Ok(ConflictPolicy::KeepFirst),
+            "keep-last" => Ok(ConflictPolicy::KeepLast),
+            "drop-record" => Ok(ConflictPolicy::DropRecord),
+            "drop-line" => Ok(ConflictPolicy::DropLine),
+            _ => Err(format!("unknown conflict policy '{}'", value))
+        }
+    }
+}
+
+/// A checksum conflict resolved by the `ConflictPolicy`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conflict {
+    pub source_file: SourceFile,
+    pub test_name: TestName,
+    pub line: LineNumber,
+    pub first: Option<CheckSum>,
+    pub last: Option<CheckSum>,
+    pub policy: ConflictPolicy
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        let checksum = |value: &Option<CheckSum>| value.clone().unwrap_or_else(|| "(none)".to_string());
+        write!(f, "{}:{} (test \"{}\"): checksum {} != {}, resolved by {:?}",
+            self.source_file, self.line, self.test_name,
+            checksum(&self.first), checksum(&self.last), self.policy)
+    }
+}
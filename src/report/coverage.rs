@@ -0,0 +1,208 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::fmt:: { Display, Formatter, Result };
+use std::ops:: { Add, AddAssign };
+use report::test:: { Test };
+use report::file:: { File };
+use report::summary:: { Summary };
+use report::counter:: { Hit, HitCounter, FoundCounter };
+
+/// Number of the found and hit items of one kind
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Coverage {
+    pub found: usize,
+    pub hit: usize
+}
+
+impl Coverage {
+    pub fn new(found: usize, hit: usize) -> Self {
+        Coverage {
+            found: found,
+            hit: hit
+        }
+    }
+    fn from_counter<T: HitCounter + FoundCounter>(counter: &T) -> Self {
+        Coverage::new(counter.found_count(), counter.hit_count())
+    }
+    fn from_hits<'a, I: Iterator<Item=&'a bool>>(hits: I) -> Self {
+        let mut coverage = Coverage::default();
+        for hit in hits {
+            coverage.found += 1;
+            coverage.hit += if *hit { 1 } else { 0 };
+        }
+        coverage
+    }
+    /// Percentage of the hit items, `None` when nothing was found
+    pub fn percentage(&self) -> Option<f64> {
+        if self.found == 0 {
+            return None;
+        }
+        Some(self.hit as f64 * 100.0 / self.found as f64)
+    }
+}
+
+impl Add for Coverage {
+    type Output = Coverage;
+
+    fn add(self, other: Coverage) -> Coverage {
+        Coverage::new(self.found + other.found, self.hit + other.hit)
+    }
+}
+
+impl AddAssign for Coverage {
+    fn add_assign(&mut self, other: Coverage) {
+        *self = *self + other;
+    }
+}
+
+impl Display for Coverage {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self.percentage() {
+            Some(percentage) => write!(f, "{:.1}% ({} of {})", percentage, self.hit, self.found),
+            None => write!(f, "no data found")
+        }
+    }
+}
+
+
+/// Line, function and branch coverage of a test, a file or a report
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::coverage:: { Coverage, CoverageSummary };
+///
+/// let summary = CoverageSummary {
+///     lines: Coverage::new(6, 5),
+///     functions: Coverage::new(2, 2),
+///     branches: Coverage::default()
+/// };
+///
+/// assert_eq!(summary.lines.percentage(), Some(5.0 * 100.0 / 6.0));
+/// assert_eq!(summary.to_string(), "lines......: 83.3% (5 of 6)\nfunctions..: 100.0% (2 of 2)\nbranches...: no data found\n");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct CoverageSummary {
+    pub lines: Coverage,
+    pub functions: Coverage,
+    pub branches: Coverage
+}
+
+impl CoverageSummary {
+    pub fn new() -> Self {
+        CoverageSummary::default()
+    }
+    /// Coverage of a single test
+    pub fn of_test(test: &Test) -> Self {
+        CoverageSummary {
+            lines: Coverage::from_counter(test.lines()),
+            functions: Coverage::from_counter(test.functions()),
+            branches: Coverage::from_counter(test.branches())
+        }
+    }
+    /// Coverage of the file over all of its tests
+    ///
+    /// An item is counted once even when several tests report it, and is hit when one of them hits it.
+    pub fn of_file(file: &File) -> Self {
+        let mut lines = BTreeMap::new();
+        let mut functions = BTreeMap::new();
+        let mut branches = BTreeMap::new();
+
+        for (_, test) in file.tests().iter() {
+            for (line_number, line) in test.lines().iter() {
+                *lines.entry(line_number).or_insert(false) |= line.is_hit();
+            }
+            for (name, function) in test.functions().iter() {
+                *functions.entry(name).or_insert(false) |= function.is_hit();
+            }
+            for (line_number, blocks) in test.branches().iter() {
+                for (unit, branch) in blocks.iter() {
+                    *branches.entry((line_number, unit)).or_insert(false) |= branch.is_hit();
+                }
+            }
+        }
+        CoverageSummary {
+            lines: Coverage::from_hits(lines.values()),
+            functions: Coverage::from_hits(functions.values()),
+            branches: Coverage::from_hits(branches.values())
+        }
+    }
+}
+
+impl Add for CoverageSummary {
+    type Output = CoverageSummary;
+
+    fn add(self, other: CoverageSummary) -> CoverageSummary {
+        CoverageSummary {
+            lines: self.lines + other.lines,
+            functions: self.functions + other.functions,
+            branches: self.branches + other.branches
+        }
+    }
+}
+
+impl AddAssign for CoverageSummary {
+    fn add_assign(&mut self, other: CoverageSummary) {
+        *self = *self + other;
+    }
+}
+
+impl Display for CoverageSummary {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        try!(writeln!(f, "lines......: {}", self.lines));
+        try!(writeln!(f, "functions..: {}", self.functions));
+        writeln!(f, "branches...: {}", self.branches)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use lcov_parser:: { LineData, FunctionName, FunctionData };
+    use merger::ops:: { TryMerge };
+    use report::test:: { Test, Tests };
+    use report::file:: { File };
+    use report::branch:: { Branch };
+    use report::coverage:: { Coverage, CoverageSummary };
+
+    fn line(line: u32, count: u32) -> LineData {
+        LineData { line: line, count: count, checksum: None }
+    }
+
+    #[test]
+    fn percentage() {
+        assert_eq!(Coverage::new(4, 1).percentage(), Some(25.0));
+        assert_eq!(Coverage::new(0, 0).percentage(), None);
+        assert_eq!(Coverage::new(4, 1) + Coverage::new(2, 2), Coverage::new(6, 3));
+    }
+
+    #[test]
+    fn of_test() {
+        let mut test = Test::new();
+        test.try_merge(&line(1, 1)).unwrap();
+        test.try_merge(&line(2, 0)).unwrap();
+        test.try_merge(&FunctionName { name: "main".to_string(), line: 1 }).unwrap();
+        test.try_merge(&FunctionData { name: "main".to_string(), count: 1 }).unwrap();
+        test.try_merge(&Branch::new(2, 0, 0, 0)).unwrap();
+
+        let summary = CoverageSummary::of_test(&test);
+        assert_eq!(summary.lines, Coverage::new(2, 1));
+        assert_eq!(summary.functions, Coverage::new(1, 1));
+        assert_eq!(summary.branches, Coverage::new(1, 0));
+    }
+
+    #[test]
+    fn of_file() {
+        let mut tests = Tests::new();
+        tests.try_merge((&"unit".to_string(), &line(1, 1))).unwrap();
+        tests.try_merge((&"unit".to_string(), &line(2, 0))).unwrap();
+        tests.try_merge((&"integration".to_string(), &line(2, 3))).unwrap();
+        tests.try_merge((&"integration".to_string(), &line(3, 0))).unwrap();
+        tests.try_merge((&"unit".to_string(), &Branch::new(2, 0, 0, 0))).unwrap();
+        tests.try_merge((&"integration".to_string(), &Branch::new(2, 0, 0, 1))).unwrap();
+
+        let summary = CoverageSummary::of_file(&File::new(tests));
+        assert_eq!(summary.lines, Coverage::new(3, 2));
+        assert_eq!(summary.branches, Coverage::new(1, 1));
+        assert_eq!(summary.functions, Coverage::new(0, 0));
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt:: { Display, Formatter, Result as FormatResult };
+use std::fs;
+use std::path:: { Path, PathBuf };
+use serde:: { Serialize };
+use report:: { Report };
+use report::summary:: { Summary };
+use report::attribute:: { LineNumber, ExecutionCount };
+
+/// The Coveralls JSON coverage payload
+///
+/// ```json
+/// {
+///   "source_files": [
+///     {
+///       "name": "src/main.c",
+///       "source_digest": "9e107d9d372bb6826bd81d3542a419d6",
+///       "coverage": [null, 2, 0],
+///       "branches": [2, 0, 0, 1, 2, 0, 1, 0]
+///     }
+///   ]
+/// }
+/// ```
+///
+/// `coverage` has an entry for every line of the source file, which is `null` for a line
+/// without code. `branches` is flat, with the line, block, branch and hits of every branch.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct CoverallsReport {
+    pub source_files: Vec<CoverallsSourceFile>
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct CoverallsSourceFile {
+    pub name: String,
+    /// MD5 of the source file, when it could be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_digest: Option<String>,
+    pub coverage: Vec<Option<ExecutionCount>>,
+    pub branches: Vec<u32>
+}
+
+/// The report as a Coveralls JSON payload, to be uploaded by a Coveralls client
+///
+/// The source files are read to compute their digests and numbers of lines;
+/// a source file that can't be read is written up to its last line with coverage, without a digest.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::merge_files;
+///
+/// let report = merge_files(&["tests/fixtures/fixture1.info"]).unwrap();
+/// let json = report.coveralls()
+///     .source_root("/home/vagrant/shared/lcov-merge")
+///     .to_string();
+///
+/// assert!(json.contains(r#""name": "tests/fixtures/func1.c""#));
+/// ```
+pub struct Coveralls<'a> {
+    report: &'a Report,
+    source_root: Option<PathBuf>
+}
+
+impl<'a> Coveralls<'a> {
+    pub fn new(report: &'a Report) -> Self {
+        Coveralls {
+            report: report,
+            source_root: None
+        }
+    }
+    /// Name the source files relative to the directory, and read the relative paths from it.
+    pub fn source_root<T: AsRef<Path>>(mut self, source_root: T) -> Self {
+        self.source_root = Some(source_root.as_ref().to_path_buf());
+        self
+    }
+    fn name(&self, source_file: &str) -> String {
+        let source_root = match self.source_root {
+            Some(ref source_root) => source_root,
+            None => return source_file.to_string()
+        };
+        match Path::new(source_file).strip_prefix(source_root) {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => source_file.to_string()
+        }
+    }
+    fn read_source(&self, name: &str) -> Option<Vec<u8>> {
+        let path = match self.source_root {
+            Some(ref source_root) => source_root.join(name),
+            None => PathBuf::from(name)
+        };
+        fs::read(path).ok()
+    }
+    pub fn payload(&self) -> CoverallsReport {
+        let source_files = self.report.files().iter().map(|(source_file, file)| {
+            let test = file.flatten();
+            let name = self.name(source_file);
+            let source = self.read_source(&name);
+
+            let last_line = test.lines().iter().map(|(line_number, _)| *line_number).max().unwrap_or(0);
+            let line_count = match source {
+                Some(ref source) => String::from_utf8_lossy(source).lines().count() as LineNumber,
+                None => last_line
+            };
+            let coverage = (1..line_count.max(last_line) + 1)
+                .map(|line_number| test.lines().get(&line_number).map(|line| *line.execution_count()))
+                .collect();
+
+            let mut branches = vec![];
+            for (_, blocks) in test.branches().iter() {
+                for (_, branch) in blocks.iter() {
+                    let hits = branch.execution_count().cloned().unwrap_or(0);
+                    branches.extend_from_slice(&[*branch.line_number(), *branch.block(), *branch.branch(), hits]);
+                }
+            }
+
+            CoverallsSourceFile {
+                name: name,
+                source_digest: source.map(|source| format!("{:x}", md5::compute(source))),
+                coverage: coverage,
+                branches: branches
+            }
+        }).collect();
+
+        CoverallsReport {
+            source_files: source_files
+        }
+    }
+}
+
+impl<'a> Display for Coveralls<'a> {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        let json = try!(serde_json::to_string_pretty(&self.payload()).map_err(|_| ::std::fmt::Error));
+        writeln!(f, "{}", json)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use merger::*;
+    use report::coveralls:: { CoverallsSourceFile };
+
+    #[test]
+    fn payload() {
+        let input = concat!(
+            "TN:\nSF:tests/fixtures/fixture.c\nBRDA:6,0,0,1\nBRDA:6,0,1,-\nDA:4,1\nDA:6,1\nDA:7,0\nend_of_record\n",
+            "TN:\nSF:/src/missing.c\nDA:2,3\nend_of_record\n"
+        );
+        let report = merge_readers(vec![Cursor::new(input.as_bytes())]).unwrap();
+        let payload = report.coveralls().source_root(".").payload();
+
+        assert_eq!(payload.source_files[0], CoverallsSourceFile {
+            name: "/src/missing.c".to_string(),
+            source_digest: None,
+            coverage: vec![None, Some(3)],
+            branches: vec![]
+        });
+
+        let fixture = &payload.source_files[1];
+        assert_eq!(fixture.name, "tests/fixtures/fixture.c");
+        assert_eq!(fixture.source_digest.as_ref().map(|digest| digest.len()), Some(32));
+        assert_eq!(fixture.coverage, vec![None, None, None, Some(1), None, Some(1), Some(0), None, None]);
+        assert_eq!(fixture.branches, vec![6, 0, 0, 1, 6, 0, 1, 0]);
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt:: { Display, Formatter, Result };
+
+/// A problem found in an input while merging in lenient mode
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::diagnostic::Diagnostic;
+///
+/// let diagnostic = Diagnostic::new("shard1.info", 12, "end_of_record without SF");
+///
+/// assert_eq!(diagnostic.to_string(), "shard1.info:12: end_of_record without SF");
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub input: String,
+    pub line: u32,
+    pub reason: String
+}
+
+impl Diagnostic {
+    pub fn new<I: Into<String>, R: Into<String>>(input: I, line: u32, reason: R) -> Self {
+        Diagnostic {
+            input: input.into(),
+            line: line,
+            reason: reason.into()
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{}:{}: {}", self.input, self.line, self.reason)
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::fmt:: { Display, Formatter, Result };
+use std::path:: { Component, Path, PathBuf };
+use report::file:: { Files };
+use report::summary:: { Summary };
+use report::coverage:: { CoverageSummary };
+use report::attribute:: { SourceFile };
+
+/// Coverage of a directory, rolled up from the source files under it
+///
+/// The root of the tree has an empty path and covers every source file of the report,
+/// with the absolute and the relative directories side by side under it.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::merge_files;
+///
+/// let report = merge_files(&["tests/fixtures/fixture1.info"]).unwrap();
+/// let root = report.directories();
+/// let fixtures = root.find("/home/vagrant/shared/lcov-merge/tests/fixtures").unwrap();
+///
+/// assert_eq!(fixtures.files().len(), 3);
+/// assert_eq!(fixtures.coverage(), root.coverage());
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Directory {
+    path: String,
+    coverage: CoverageSummary,
+    directories: BTreeMap<String, Directory>,
+    files: BTreeMap<SourceFile, CoverageSummary>
+}
+
+impl Directory {
+    pub fn new<T: Into<String>>(path: T) -> Self {
+        Directory {
+            path: path.into(),
+            coverage: CoverageSummary::new(),
+            directories: BTreeMap::new(),
+            files: BTreeMap::new()
+        }
+    }
+    /// Group the source files by the components of their directories.
+    pub fn from_files(files: &Files) -> Self {
+        let mut root = Directory::new("");
+        for (source_file, file) in files.iter() {
+            let parent = Path::new(source_file).parent().unwrap_or_else(|| Path::new(""));
+            root.add(parent, source_file.clone(), file.coverage());
+        }
+        root
+    }
+    fn add(&mut self, parent: &Path, source_file: SourceFile, coverage: CoverageSummary) {
+        self.coverage += coverage;
+
+        let mut directory = self;
+        for (name, directory_path) in subdirectories(parent).into_iter() {
+            directory = directory.directories.entry(name)
+                .or_insert_with(|| Directory::new(directory_path));
+            directory.coverage += coverage;
+        }
+        directory.files.insert(source_file, coverage);
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn coverage(&self) -> CoverageSummary {
+        self.coverage
+    }
+    /// Subdirectories keyed by their names
+    pub fn directories(&self) -> &BTreeMap<String, Directory> {
+        &self.directories
+    }
+    /// Coverage of the source files directly in the directory, keyed by their paths in the report
+    pub fn files(&self) -> &BTreeMap<SourceFile, CoverageSummary> {
+        &self.files
+    }
+    /// Find the directory of the path under this one.
+    pub fn find<T: AsRef<Path>>(&self, path: T) -> Option<&Directory> {
+        subdirectories(path.as_ref()).into_iter().try_fold(self, |directory, (name, _)| {
+            directory.directories.get(&name)
+        })
+    }
+    /// The directory and all of its subdirectories, depth first in path order
+    pub fn walk(&self) -> Vec<&Directory> {
+        let mut directories = vec![self];
+        for (_, subdirectory) in self.directories.iter() {
+            directories.extend(subdirectory.walk());
+        }
+        directories
+    }
+}
+
+/// Names and paths of the directories down to the path
+///
+/// The root of an absolute path is part of the name of its first directory (`/src`), so that
+/// it doesn't become a directory of its own, and `.` components are skipped.
+fn subdirectories(path: &Path) -> Vec<(String, String)> {
+    let mut subdirectories = vec![];
+    let mut directory_path = PathBuf::new();
+    let mut root = String::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => continue,
+            Component::Prefix(_) | Component::RootDir => {
+                directory_path.push(component.as_os_str());
+                root.push_str(&component.as_os_str().to_string_lossy());
+            },
+            Component::ParentDir | Component::Normal(_) => {
+                directory_path.push(component.as_os_str());
+                let name = format!("{}{}", root, component.as_os_str().to_string_lossy());
+                root.clear();
+                subdirectories.push((name, directory_path.to_string_lossy().into_owned()));
+            }
+        }
+    }
+    subdirectories
+}
+
+/// One line per directory of the tree, the root written as `.`: `<path>: lines <coverage>, functions <coverage>, branches <coverage>`
+impl Display for Directory {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for directory in self.walk().into_iter() {
+            let coverage = directory.coverage();
+            let path = if directory.path().is_empty() { "." } else { directory.path() };
+            try!(writeln!(f, "{}: lines {}, functions {}, branches {}",
+                path, coverage.lines, coverage.functions, coverage.branches));
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use lcov_parser:: { LineData };
+    use merger::ops:: { TryMerge };
+    use report::test:: { Tests };
+    use report::file:: { File, Files };
+    use report::coverage:: { Coverage };
+    use report::directory:: { Directory };
+
+    fn file(lines: &[(u32, u32)]) -> File {
+        let mut tests = Tests::new();
+        for &(line, count) in lines.iter() {
+            let data = LineData { line: line, count: count, checksum: None };
+            tests.try_merge((&String::new(), &data)).unwrap();
+        }
+        File::new(tests)
+    }
+
+    fn files() -> Files {
+        let mut files = Files::new();
+        files.try_merge((&"src/report/mod.rs".to_string(), &file(&[(1, 1), (2, 0)]))).unwrap();
+        files.try_merge((&"src/report/line.rs".to_string(), &file(&[(1, 1)]))).unwrap();
+        files.try_merge((&"src/lib.rs".to_string(), &file(&[(1, 0)]))).unwrap();
+        files
+    }
+
+    #[test]
+    fn rollup() {
+        let root = Directory::from_files(&files());
+
+        assert_eq!(root.coverage().lines, Coverage::new(4, 2));
+        assert_eq!(root.find("src").unwrap().coverage().lines, Coverage::new(4, 2));
+        assert_eq!(root.find("src/report").unwrap().coverage().lines, Coverage::new(3, 2));
+        assert_eq!(root.find("src/report").unwrap().files().len(), 2);
+        assert!(root.find("src/merger").is_none());
+
+        let paths: Vec<&str> = root.walk().iter().map(|directory| directory.path()).collect();
+        assert_eq!(paths, vec!["", "src", "src/report"]);
+    }
+
+    #[test]
+    fn display() {
+        let root = Directory::from_files(&files());
+        assert_eq!(root.to_string(), concat!(
+            ".: lines 50.0% (2 of 4), functions no data found, branches no data found\n",
+            "src: lines 50.0% (2 of 4), functions no data found, branches no data found\n",
+            "src/report: lines 66.7% (2 of 3), functions no data found, branches no data found\n"
+        ));
+    }
+
+    #[test]
+    fn display_parents() {
+        let mut files = Files::new();
+        files.try_merge((&"/src/report/line.rs".to_string(), &file(&[(1, 1), (2, 0)]))).unwrap();
+        let root = Directory::from_files(&files);
+
+        let lines: Vec<String> = root.to_string().lines().map(|line| line.to_string()).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], ".: lines 50.0% (1 of 2), functions no data found, branches no data found");
+        assert_eq!(lines[1], "/src: lines 50.0% (1 of 2), functions no data found, branches no data found");
+    }
+
+    #[test]
+    fn absolute_and_relative_paths() {
+        let mut files = files();
+        files.try_merge((&"/src/report/mod.rs".to_string(), &file(&[(1, 1)]))).unwrap();
+        files.try_merge((&"./src/main.rs".to_string(), &file(&[(1, 1)]))).unwrap();
+        let root = Directory::from_files(&files);
+
+        let paths: Vec<&str> = root.walk().iter().map(|directory| directory.path()).collect();
+        assert_eq!(paths, vec!["", "/src", "/src/report", "src", "src/report"]);
+        assert_eq!(root.coverage().lines, Coverage::new(6, 4));
+        assert_eq!(root.find("/src/report").unwrap().files().len(), 1);
+        assert_eq!(root.find("./src").unwrap().files().len(), 2);
+    }
+}
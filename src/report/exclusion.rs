@@ -0,0 +1,159 @@
+use std::collections::btree_set:: { BTreeSet };
+use std::fs:: { File };
+use std::io:: { BufRead, BufReader, Result as IOResult };
+use std::path::Path;
+use report::attribute:: { LineNumber };
+
+/// Lines of a source file excluded from the coverage by the markers of lcov
+///
+/// * `LCOV_EXCL_LINE` excludes the line, `LCOV_EXCL_START` to `LCOV_EXCL_STOP` the lines in between.
+/// * `LCOV_EXCL_BR_LINE` excludes the branches of the line, `LCOV_EXCL_BR_START` to `LCOV_EXCL_BR_STOP`
+///   the branches of the lines in between.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::exclusion::Exclusions;
+///
+/// let source = "int main() {\n  abort(); // LCOV_EXCL_LINE\n  return x ? 0 : 1; // LCOV_EXCL_BR_LINE\n}\n";
+/// let exclusions = Exclusions::from_reader(source.as_bytes()).unwrap();
+///
+/// assert!(exclusions.is_line_excluded(&2));
+/// assert!(!exclusions.is_line_excluded(&3));
+/// assert!(exclusions.is_branch_excluded(&3));
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Exclusions {
+    lines: BTreeSet<LineNumber>,
+    branch_lines: BTreeSet<LineNumber>
+}
+
+impl Exclusions {
+    pub fn new() -> Self {
+        Exclusions {
+            lines: BTreeSet::new(),
+            branch_lines: BTreeSet::new()
+        }
+    }
+    /// Read the markers of the source file.
+    ///
+    /// A region that is never closed extends to the end of the file.
+    pub fn from_reader<R: BufRead>(reader: R) -> IOResult<Self> {
+        let mut exclusions = Exclusions::new();
+        let mut in_region = false;
+        let mut in_branch_region = false;
+
+        for (index, line) in reader.split(b'\n').enumerate() {
+            let line = try!(line);
+            let line = String::from_utf8_lossy(&line);
+            let line_number = index as LineNumber + 1;
+
+            if line.contains("LCOV_EXCL_START") {
+                in_region = true;
+            }
+            if line.contains("LCOV_EXCL_BR_START") {
+                in_branch_region = true;
+            }
+            if in_region || line.contains("LCOV_EXCL_LINE") {
+                exclusions.lines.insert(line_number);
+            }
+            if in_branch_region || line.contains("LCOV_EXCL_BR_LINE") {
+                exclusions.branch_lines.insert(line_number);
+            }
+            if line.contains("LCOV_EXCL_STOP") {
+                in_region = false;
+            }
+            if line.contains("LCOV_EXCL_BR_STOP") {
+                in_branch_region = false;
+            }
+        }
+        Ok(exclusions)
+    }
+    pub fn from_path<T: AsRef<Path>>(path: T) -> IOResult<Self> {
+        let file = try!(File::open(path));
+        Exclusions::from_reader(BufReader::new(file))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.branch_lines.is_empty()
+    }
+    /// Whether the line, and the functions and branches on it, are excluded
+    pub fn is_line_excluded(&self, line_number: &LineNumber) -> bool {
+        self.lines.contains(line_number)
+    }
+    /// Whether the branches of the line are excluded
+    pub fn is_branch_excluded(&self, line_number: &LineNumber) -> bool {
+        self.is_line_excluded(line_number) || self.branch_lines.contains(line_number)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use merger::*;
+    use report::exclusion:: { Exclusions };
+    use std::io::Cursor;
+    use std::path::Path;
+
+    #[test]
+    fn regions() {
+        let source = concat!(
+            "int main() {\n",
+            "  // LCOV_EXCL_START\n",
+            "  debug();\n",
+            "  // LCOV_EXCL_STOP\n",
+            "  run();\n",
+            "  // LCOV_EXCL_BR_START\n",
+            "  if (x) y();\n",
+            "  // LCOV_EXCL_BR_STOP\n",
+            "}\n"
+        );
+        let exclusions = Exclusions::from_reader(source.as_bytes()).unwrap();
+
+        let lines: Vec<u32> = (1..10).filter(|line| exclusions.is_line_excluded(line)).collect();
+        let branch_lines: Vec<u32> = (1..10).filter(|line| exclusions.is_branch_excluded(line)).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+        assert_eq!(branch_lines, vec![2, 3, 4, 6, 7, 8]);
+    }
+
+    #[test]
+    fn unterminated_region() {
+        let source = "a();\n// LCOV_EXCL_START\nb();\nc();";
+        let exclusions = Exclusions::from_reader(source.as_bytes()).unwrap();
+        assert!(!exclusions.is_line_excluded(&1));
+        assert!(exclusions.is_line_excluded(&4));
+    }
+
+    #[test]
+    fn exclude_marked() {
+        let input = concat!(
+            "TN:\nSF:tests/fixtures/excluded.c\n",
+            "FN:3,debug\nFN:8,main\nFNDA:0,debug\nFNDA:1,main\n",
+            "BRDA:10,0,0,0\nBRDA:10,0,1,1\nBRDA:11,0,0,1\nBRDA:11,0,1,0\n",
+            "DA:5,0\nDA:10,1\nDA:11,1\nDA:12,1\n",
+            "end_of_record\n",
+            "TN:\nSF:tests/fixtures/missing.c\nDA:1,1\nend_of_record\n"
+        );
+        let mut parse = ReportMerger::new();
+        let report = parse.merge_reader(Cursor::new(input.as_bytes())).unwrap().exclude_marked(None);
+
+        assert_eq!(report.to_string(), concat!(
+            "TN:\nSF:tests/fixtures/excluded.c\nFN:8,main\nFNDA:1,main\nFNF:1\nFNH:1\nDA:11,1\nDA:12,1\nLF:2\nLH:2\nend_of_record\n",
+            "TN:\nSF:tests/fixtures/missing.c\nDA:1,1\nLF:1\nLH:1\nend_of_record\n"
+        ));
+        assert_eq!(report.diagnostics().len(), 1);
+        assert_eq!(report.diagnostics()[0].input, "tests/fixtures/missing.c");
+    }
+
+    #[test]
+    fn exclude_marked_under_root() {
+        let input = "TN:\nSF:fixtures/excluded.c\nDA:5,0\nDA:11,1\nend_of_record\n";
+        let mut parse = ReportMerger::new();
+        let report = parse.merge_reader(Cursor::new(input.as_bytes())).unwrap();
+
+        let excluded = report.exclude_marked(Some(Path::new("tests")));
+        assert_eq!(excluded.to_string(), "TN:\nSF:fixtures/excluded.c\nDA:11,1\nLF:1\nLH:1\nend_of_record\n");
+        assert!(excluded.diagnostics().is_empty());
+
+        assert_eq!(report.exclude_marked(None).diagnostics().len(), 1);
+    }
+}
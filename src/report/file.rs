@@ -1,8 +1,9 @@
 use std::collections::btree_map:: { BTreeMap };
-use merger::ops:: { TryMerge, MergeResult, TestError };
+use merger::ops:: { Merge, TryMerge, MergeResult, TestError };
 use report::test:: { Test, Tests };
 use report::summary:: { Summary };
 use report::attribute:: { SourceFile };
+use report::coverage:: { CoverageSummary };
 
 #[derive(Debug, Clone)]
 pub struct File {
@@ -18,9 +19,36 @@ impl File {
     pub fn tests(&self) -> &Tests {
         &self.tests
     }
+    pub fn tests_mut(&mut self) -> &mut Tests {
+        &mut self.tests
+    }
     pub fn get_test(&self, name: &String) -> Option<&Test> {
         self.tests.get(name)
     }
+    /// Coverage over all tests of the file
+    pub fn coverage(&self) -> CoverageSummary {
+        CoverageSummary::of_file(self)
+    }
+    /// All tests of the file in a single test, summing up the execution counts.
+    ///
+    /// The checksums of the lines are not kept.
+    pub fn flatten(&self) -> Test {
+        let mut flattened = Test::new();
+        for (_, test) in self.tests.iter() {
+            for (_, line) in test.lines().iter() {
+                flattened.lines_mut().merge(line);
+            }
+            for (_, function) in test.functions().iter() {
+                flattened.functions_mut().merge(function);
+            }
+            for (_, blocks) in test.branches().iter() {
+                for (_, branch) in blocks.iter() {
+                    flattened.branches_mut().merge(branch);
+                }
+            }
+        }
+        flattened
+    }
 }
 
 impl<'a> TryMerge<&'a File> for File {
@@ -47,6 +75,7 @@ impl Files {
 }
 
 impl_summary!(Files, files<SourceFile, File>);
+impl_summary_mut!(Files, files<SourceFile, File>);
 
 
 impl<'a> TryMerge<(&'a SourceFile, &'a File)> for Files {
@@ -63,3 +92,32 @@ impl<'a> TryMerge<(&'a SourceFile, &'a File)> for Files {
 }
 
 impl_try_merge_self_summary!(Files:files, TestError);
+
+
+#[cfg(test)]
+mod tests {
+    use lcov_parser:: { LineData };
+    use merger::ops:: { TryMerge };
+    use report::test:: { Tests };
+    use report::file:: { File };
+    use report::branch:: { Branch };
+    use report::function:: { Function };
+    use report::summary:: { Summary };
+
+    #[test]
+    fn flatten() {
+        let mut tests = Tests::new();
+        for &(test_name, count, checksum) in [("unit", u32::MAX, "aaa"), ("integration", 2, "bbb")].iter() {
+            let test_name = test_name.to_string();
+            tests.try_merge((&test_name, &LineData { line: 1, count: count, checksum: Some(checksum.to_string()) })).unwrap();
+            tests.try_merge((&test_name, &Function::new("main".to_string(), 1, count))).unwrap();
+            tests.try_merge((&test_name, &Branch::new(1, 0, 0, count))).unwrap();
+        }
+        let test = File::new(tests).flatten();
+
+        let line = test.lines().get(&1).unwrap();
+        assert_eq!((*line.execution_count(), line.checksum()), (u32::MAX, None));
+        assert_eq!(test.functions().get(&"main".to_string()).unwrap().execution_count(), &u32::MAX);
+        assert_eq!(test.branches().get(&1).unwrap().iter().next().unwrap().1.execution_count(), Some(&u32::MAX));
+    }
+}
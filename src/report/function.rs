@@ -1,10 +1,12 @@
 use std::io;
 use std::cmp::PartialEq;
 use std::collections::btree_map:: { BTreeMap };
+use std::default:: { Default };
 use std::convert::{ From };
 use std::fmt:: { Display, Formatter, Result };
+use std::str:: { FromStr };
 use lcov_parser:: { FunctionName as FunctionNameRecord, FunctionData };
-use merger::ops:: { TryMerge, MergeResult, FunctionError };
+use merger::ops:: { Merge, TryMerge, MergeResult, FunctionError };
 use record:: { RecordWrite };
 use report::summary:: { Summary };
 use report::attribute:: { ExecutionCount, FunctionName, LineNumber };
@@ -24,7 +26,37 @@ impl Functions {
     }
 }
 
+impl Functions {
+    /// Group the functions sharing the same location, which are aliases of one another.
+    ///
+    /// The groups are ordered by the location of the functions.
+    pub fn aliases(&self) -> Vec<FunctionAliases<'_>> {
+        let mut groups: BTreeMap<(LineNumber, Option<LineNumber>), Vec<&Function>> = BTreeMap::new();
+        for (_, function) in self.iter() {
+            let location = (function.line_number, function.end_line_number);
+            groups.entry(location).or_default().push(function);
+        }
+        groups.into_iter()
+            .map(|((line_number, end_line_number), functions)| {
+                FunctionAliases {
+                    line_number: line_number,
+                    end_line_number: end_line_number,
+                    functions: functions
+                }
+            })
+            .collect()
+    }
+    /// Records of the functions in the syntax of the format
+    pub fn records(&self, format: FunctionFormat) -> FunctionRecords<'_> {
+        FunctionRecords {
+            functions: self,
+            format: format
+        }
+    }
+}
+
 impl_summary!(Functions, functions<FunctionName, Function>);
+impl_summary_mut!(Functions, functions<FunctionName, Function>);
 
 impl HitCounter for Functions {
     fn hit_count(&self) -> usize {
@@ -52,15 +84,69 @@ impl RecordWrite for Functions {
 
 impl Display for Functions {
     fn fmt(&self, f: &mut Formatter) -> Result {
-        if self.is_empty() {
+        write!(f, "{}", self.records(FunctionFormat::Legacy))
+    }
+}
+
+
+/// Syntax of the function records
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum FunctionFormat {
+    /// `FN:<line>,<name>` and `FNDA:<count>,<name>`
+    #[default]
+    Legacy,
+    /// `FNL:<index>,<line>[,<end line>]` and `FNA:<index>,<count>,<name>` of LCOV 2.x,
+    /// where `FNF`/`FNH` count the functions once for all of their aliases
+    Lcov2
+}
+
+impl FromStr for FunctionFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> ::std::result::Result<Self, Self::Err> {
+        match value {
+            "legacy" => Ok(FunctionFormat::Legacy),
+            "lcov2" => Ok(FunctionFormat::Lcov2),
+            _ => Err(format!("unknown function format '{}'", value))
+        }
+    }
+}
+
+pub struct FunctionRecords<'a> {
+    functions: &'a Functions,
+    format: FunctionFormat
+}
+
+impl<'a> Display for FunctionRecords<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        if self.functions.is_empty() {
             return Ok(());
         }
-        for (_, function) in self.iter() {
-            try!(writeln!(f, "FN:{},{}", function.line_number(), function.name()));
-            try!(writeln!(f, "FNDA:{},{}", function.execution_count(), function.name()));
+        match self.format {
+            FunctionFormat::Legacy => {
+                for (_, function) in self.functions.iter() {
+                    try!(writeln!(f, "FN:{},{}", function.line_number(), function.name()));
+                    try!(writeln!(f, "FNDA:{},{}", function.execution_count(), function.name()));
+                }
+                try!(writeln!(f, "FNF:{}", self.functions.found_count()));
+                try!(writeln!(f, "FNH:{}", self.functions.hit_count()));
+            },
+            FunctionFormat::Lcov2 => {
+                let aliases = self.functions.aliases();
+                for (index, group) in aliases.iter().enumerate() {
+                    match group.end_line_number() {
+                        Some(end_line_number) => try!(writeln!(f, "FNL:{},{},{}",
+                            index, group.line_number(), end_line_number)),
+                        None => try!(writeln!(f, "FNL:{},{}", index, group.line_number()))
+                    }
+                    for function in group.functions().iter() {
+                        try!(writeln!(f, "FNA:{},{},{}", index, function.execution_count(), function.name()));
+                    }
+                }
+                try!(writeln!(f, "FNF:{}", aliases.len()));
+                try!(writeln!(f, "FNH:{}", aliases.iter().filter(|group| group.is_hit()).count()));
+            }
         }
-        try!(writeln!(f, "FNF:{}", self.hit_count()));
-        try!(writeln!(f, "FNH:{}", self.found_count()));
         Ok(())
     }
 }
@@ -100,13 +186,36 @@ impl<'a> TryMerge<&'a FunctionNameRecord> for Functions {
     }
 }
 
+impl<'a> TryMerge<&'a Function> for Functions {
+    type Err = FunctionError;
+
+    fn try_merge(&mut self, function: &'a Function) -> MergeResult<Self::Err> {
+        if !self.functions.contains_key(&function.name) {
+            self.functions.insert(function.name.clone(), function.clone());
+            return Ok(());
+        }
+        let function_to_merge = self.functions.get_mut(&function.name).unwrap();
+        function_to_merge.try_merge(function)
+    }
+}
+
 impl_try_merge_self_summary!(Functions:functions, FunctionError);
 
+/// Sum up the execution counts by function name.
+impl<'a> Merge<&'a Function> for Functions {
+    fn merge(&mut self, function: &'a Function) {
+        self.functions.entry(function.name.clone())
+            .or_insert_with(|| Function::new(function.name.clone(), 0, 0))
+            .add(function);
+    }
+}
+
 
 #[derive(Debug, Clone)]
 pub struct Function {
     name: FunctionName,
     line_number: LineNumber,
+    end_line_number: Option<LineNumber>,
     execution_count: ExecutionCount
 }
 
@@ -119,21 +228,65 @@ impl Function {
         Function {
             name: name,
             line_number: line_number,
+            end_line_number: None,
             execution_count: execution_count
         }
     }
+    pub fn with_end_line_number(mut self, end_line_number: Option<LineNumber>) -> Self {
+        self.end_line_number = end_line_number;
+        self
+    }
     pub fn name(&self) -> &FunctionName {
         &self.name
     }
     pub fn line_number(&self) -> &LineNumber {
         &self.line_number
     }
+    pub fn end_line_number(&self) -> Option<&LineNumber> {
+        self.end_line_number.as_ref()
+    }
     pub fn execution_count(&self) -> &ExecutionCount {
         &self.execution_count
     }
     pub fn is_hit(&self) -> bool {
         self.execution_count.is_hit()
     }
+    /// Add the execution count of the other function, and its lines when unknown.
+    fn add(&mut self, other: &Function) {
+        if self.line_number == 0 {
+            self.line_number = other.line_number;
+        }
+        if self.end_line_number.is_none() {
+            self.end_line_number = other.end_line_number;
+        }
+        self.execution_count = self.execution_count.saturating_add(other.execution_count);
+    }
+}
+
+
+/// Functions that share the same location
+pub struct FunctionAliases<'a> {
+    line_number: LineNumber,
+    end_line_number: Option<LineNumber>,
+    functions: Vec<&'a Function>
+}
+
+impl<'a> FunctionAliases<'a> {
+    pub fn line_number(&self) -> &LineNumber {
+        &self.line_number
+    }
+    pub fn end_line_number(&self) -> Option<&LineNumber> {
+        self.end_line_number.as_ref()
+    }
+    pub fn functions(&self) -> &[&'a Function] {
+        &self.functions
+    }
+    pub fn execution_count(&self) -> ExecutionCount {
+        self.functions.iter().fold(0, |count, function| count.saturating_add(function.execution_count))
+    }
+    pub fn is_hit(&self) -> bool {
+        self.functions.iter().any(|function| function.is_hit())
+    }
 }
 
 impl<'a> From<&'a FunctionData> for Function {
@@ -191,7 +344,7 @@ impl<'a> TryMerge<&'a FunctionData> for Function {
                 other.name.clone()
             ));
         }
-        self.execution_count += other.count;
+        self.execution_count = self.execution_count.saturating_add(other.count);
         Ok(())
     }
 }
@@ -207,7 +360,7 @@ impl<'a> TryMerge<&'a Function> for Function {
                 other.name().clone()
             ));
         }
-        self.execution_count += *other.execution_count();
+        self.add(other);
         Ok(())
     }
 }
@@ -218,7 +371,7 @@ impl<'a> TryMerge<&'a Function> for Function {
 #[cfg(test)]
 mod tests {
     use lcov_parser:: { FunctionData };
-    use report::function:: { Function, Functions };
+    use report::function:: { Function, Functions, FunctionFormat };
     use report::summary:: { Summary };
     use report::counter:: { FoundCounter, HitCounter };
     use merger::ops::*;
@@ -254,4 +407,42 @@ mod tests {
         assert_eq!( functions.hit_count(), 1 );
         assert_eq!( functions.found_count(), 2 );
     }
+
+    #[test]
+    fn aliases() {
+        let mut functions = Functions::new();
+        functions.try_merge(&Function::new("main".to_string(), 4, 1).with_end_line_number(Some(10))).unwrap();
+        functions.try_merge(&Function::new("_main".to_string(), 4, 0).with_end_line_number(Some(10))).unwrap();
+        functions.try_merge(&Function::new("foo".to_string(), 12, 0)).unwrap();
+
+        let aliases = functions.aliases();
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases[0].functions().len(), 2);
+        assert_eq!(aliases[0].execution_count(), 1);
+        assert_eq!(aliases[1].end_line_number(), None);
+    }
+
+    #[test]
+    fn found_and_hit_records() {
+        let mut functions = Functions::new();
+        functions.try_merge(&Function::new("main".to_string(), 4, 1)).unwrap();
+        functions.try_merge(&Function::new("foo".to_string(), 12, 0)).unwrap();
+        functions.try_merge(&Function::new("bar".to_string(), 20, 0)).unwrap();
+
+        let records = functions.records(FunctionFormat::Legacy).to_string();
+        assert!(records.ends_with("FNF:3\nFNH:1\n"));
+    }
+
+    #[test]
+    fn records() {
+        let mut functions = Functions::new();
+        functions.try_merge(&Function::new("main".to_string(), 4, 1).with_end_line_number(Some(10))).unwrap();
+        functions.try_merge(&Function::new("_main".to_string(), 4, 0).with_end_line_number(Some(10))).unwrap();
+        functions.try_merge(&Function::new("foo".to_string(), 12, 0)).unwrap();
+
+        assert_eq!(functions.records(FunctionFormat::Legacy).to_string(),
+            "FN:4,_main\nFNDA:0,_main\nFN:12,foo\nFNDA:0,foo\nFN:4,main\nFNDA:1,main\nFNF:3\nFNH:1\n");
+        assert_eq!(functions.records(FunctionFormat::Lcov2).to_string(),
+            "FNL:0,4,10\nFNA:0,0,_main\nFNA:0,1,main\nFNL:1,12\nFNA:1,0,foo\nFNF:2\nFNH:1\n");
+    }
 }
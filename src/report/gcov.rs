@@ -0,0 +1,161 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::io:: { Read };
+use std::path:: { Path };
+use serde:: { Deserialize };
+use serde_json:: { Error as JsonError, Value };
+use lcov_parser:: { LineData };
+use report::branch:: { Branch };
+use report::function:: { Function };
+use report::attribute:: { LineNumber, execution_count };
+
+/// The intermediate format of `gcov --json-format` (GCC 9 and later)
+///
+/// Only the coverage data is read; block counts, columns and the like are ignored.
+///
+/// ```json
+/// {
+///   "format_version": "1",
+///   "gcc_version": "12.2.0",
+///   "current_working_directory": "/src",
+///   "data_file": "main.gcda",
+///   "files": [{
+///     "file": "main.c",
+///     "functions": [{ "name": "_Z4mainv", "demangled_name": "main()", "start_line": 1, "end_line": 4, "execution_count": 1 }],
+///     "lines": [{ "line_number": 2, "count": 1, "branches": [{ "count": 1, "fallthrough": true, "throw": false }] }]
+///   }]
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct GcovReport {
+    pub format_version: String,
+    pub gcc_version: String,
+    #[serde(default)]
+    pub current_working_directory: Option<String>,
+    #[serde(default)]
+    pub data_file: Option<String>,
+    pub files: Vec<GcovFile>
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct GcovFile {
+    pub file: String,
+    #[serde(default)]
+    pub functions: Vec<GcovFunction>,
+    #[serde(default)]
+    pub lines: Vec<GcovLine>
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct GcovFunction {
+    pub name: String,
+    #[serde(default)]
+    pub demangled_name: Option<String>,
+    pub start_line: LineNumber,
+    #[serde(default)]
+    pub end_line: Option<LineNumber>,
+    pub execution_count: u64
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct GcovLine {
+    pub line_number: LineNumber,
+    pub count: u64,
+    #[serde(default)]
+    pub branches: Vec<GcovBranch>
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct GcovBranch {
+    pub count: u64,
+    #[serde(default)]
+    pub fallthrough: bool,
+    #[serde(default)]
+    pub throw: bool
+}
+
+impl GcovReport {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, JsonError> {
+        serde_json::from_reader(reader)
+    }
+    pub fn from_value(value: Value) -> Result<Self, JsonError> {
+        serde_json::from_value(value)
+    }
+    /// Path of the source file, under the working directory of gcov when it is relative
+    pub fn source_file(&self, file: &GcovFile) -> String {
+        match self.current_working_directory {
+            Some(ref directory) if !Path::new(&file.file).is_absolute() => {
+                Path::new(directory).join(&file.file).to_string_lossy().into_owned()
+            },
+            _ => file.file.clone()
+        }
+    }
+}
+
+impl GcovFile {
+    /// Lines of the file; a line listed more than once (e.g. by template instances) is summed up when merged.
+    pub fn to_lines(&self) -> Vec<LineData> {
+        self.lines.iter().map(|line| {
+            LineData { line: line.line_number, count: execution_count(line.count), checksum: None }
+        }).collect()
+    }
+    /// Functions of the file, named by their demangled names when gcov knows them
+    pub fn to_functions(&self) -> Vec<Function> {
+        self.functions.iter().map(|function| {
+            let name = function.demangled_name.as_ref().unwrap_or(&function.name).clone();
+            Function::new(name, function.start_line, execution_count(function.execution_count))
+                .with_end_line_number(function.end_line)
+        }).collect()
+    }
+    /// Branches of the file, numbered like `geninfo` does
+    ///
+    /// Every listing of a line is a block of its own, and the branches of a line that was never
+    /// executed were never evaluated.
+    pub fn to_branches(&self) -> Vec<Branch> {
+        let mut blocks = BTreeMap::new();
+        let mut branches = vec![];
+        for line in self.lines.iter() {
+            if line.branches.is_empty() {
+                continue;
+            }
+            let block = blocks.entry(line.line_number).or_insert(0);
+            for (index, branch) in line.branches.iter().enumerate() {
+                let index = index as u32;
+                branches.push(match line.count {
+                    0 => Branch::not_evaluated(line.line_number, *block, index),
+                    _ => Branch::new(line.line_number, *block, index, execution_count(branch.count))
+                });
+            }
+            *block += 1;
+        }
+        branches
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use report::gcov:: { GcovReport };
+
+    #[test]
+    fn template_instances() {
+        let json = r#"{ "format_version": "1", "gcc_version": "12.2.0", "current_working_directory": "/src", "files": [{
+            "file": "include/vector.h",
+            "functions": [{ "name": "_ZN3VecIiE4pushEi", "demangled_name": "Vec<int>::push(int)", "start_line": 3, "end_line": 6, "execution_count": 5000000000 }],
+            "lines": [
+                { "line_number": 4, "count": 2, "branches": [{ "count": 2, "fallthrough": true, "throw": false }, { "count": 0, "fallthrough": false, "throw": false }] },
+                { "line_number": 4, "count": 0, "branches": [{ "count": 0, "fallthrough": true, "throw": false }] }
+            ]
+        }] }"#;
+        let report = GcovReport::from_reader(json.as_bytes()).unwrap();
+        let file = &report.files[0];
+
+        assert_eq!(report.source_file(file), "/src/include/vector.h");
+        assert_eq!(file.to_functions()[0].name(), "Vec<int>::push(int)");
+        assert_eq!(*file.to_functions()[0].execution_count(), u32::MAX);
+        assert_eq!(file.to_functions()[0].end_line_number(), Some(&6));
+        let branches: Vec<(u32, u32, u32, Option<u32>)> = file.to_branches().iter()
+            .map(|branch| (*branch.line_number(), *branch.block(), *branch.branch(), branch.execution_count().cloned()))
+            .collect();
+        assert_eq!(branches, vec![(4, 0, 0, Some(2)), (4, 0, 1, Some(0)), (4, 1, 0, None)]);
+    }
+}
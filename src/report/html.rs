@@ -0,0 +1,291 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::fmt:: { Write as FormatWrite };
+use std::fs:: { self, File as OutputFile };
+use std::io:: { Result as IOResult, Write };
+use std::path:: { Path, PathBuf };
+use report:: { Report };
+use report::file:: { File };
+use report::test:: { Test };
+use report::summary:: { Summary };
+use report::coverage:: { Coverage, CoverageSummary };
+use report::counter:: { Hit };
+use report::attribute:: { LineNumber };
+use report::xml:: { escape };
+
+const STYLE: &'static str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; }
+a { color: #0645ad; }
+table { border-collapse: collapse; }
+table.summary td, table.summary th { padding: 0.2em 0.8em; border: 1px solid #ccc; text-align: right; }
+table.summary td.name, table.summary th.name { text-align: left; }
+table.summary tr.directory td { font-weight: bold; background: #eee; }
+td.high { background: #a7fc9d; }
+td.medium { background: #ffea20; }
+td.low { background: #ff9090; }
+table.source { font-family: monospace; white-space: pre; width: 100%; }
+table.source td { padding: 0 0.5em; }
+table.source td.line-number, table.source td.count { text-align: right; color: #666; border-right: 1px solid #ccc; }
+table.source td.branches { color: #666; border-right: 1px solid #ccc; }
+tr.hit td.source { background: #cad7fe; }
+tr.miss td.source { background: #ff9090; }
+span.taken { color: #080; }
+span.not-taken { color: #c00; }
+span.not-evaluated { color: #999; }
+";
+
+/// Offline HTML pages of the report, like `genhtml`
+///
+/// `index.html` lists the coverage of every directory and source file, and every source file
+/// has a page with its annotated source, its functions and its branches.
+/// The pages embed their styles, so they can be browsed without a network connection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use lcov_merge::merge_files;
+///
+/// let report = merge_files(&["tests/fixtures/fixture1.info"]).unwrap();
+/// report.html()
+///     .source_root("/home/vagrant/shared/lcov-merge")
+///     .title("lcov-merge")
+///     .write_to("coverage")
+///     .unwrap();
+/// ```
+pub struct Html<'a> {
+    report: &'a Report,
+    source_root: Option<PathBuf>,
+    title: String
+}
+
+impl<'a> Html<'a> {
+    pub fn new(report: &'a Report) -> Self {
+        Html {
+            report: report,
+            source_root: None,
+            title: "Coverage report".to_string()
+        }
+    }
+    /// Read the source files with relative paths from the directory, the current directory by default.
+    pub fn source_root<T: AsRef<Path>>(mut self, source_root: T) -> Self {
+        self.source_root = Some(source_root.as_ref().to_path_buf());
+        self
+    }
+    pub fn title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = title.into();
+        self
+    }
+    /// Write `index.html` and the pages of the source files into the directory.
+    pub fn write_to<T: AsRef<Path>>(&self, output_dir: T) -> IOResult<()> {
+        let output_dir = output_dir.as_ref();
+        try!(fs::create_dir_all(output_dir.join("files")));
+
+        let pages = self.pages();
+        try!(write_file(&output_dir.join("index.html"), &self.index(&pages)));
+        for (source_file, file) in self.report.files().iter() {
+            let source = self.read_source(source_file);
+            let page = self.file_page(source_file, file, source.as_deref());
+            try!(write_file(&output_dir.join(&pages[source_file.as_str()]), &page));
+        }
+        Ok(())
+    }
+    /// Paths of the pages of the source files relative to the output directory, keyed by the source files
+    fn pages(&self) -> BTreeMap<&str, String> {
+        self.report.files().iter().enumerate().map(|(index, (source_file, _))| {
+            let name: String = source_file.chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+                .collect();
+            (source_file.as_str(), format!("files/{}-{}.html", index + 1, name.trim_matches('_')))
+        }).collect()
+    }
+    fn read_source(&self, source_file: &str) -> Option<String> {
+        let path = match self.source_root {
+            Some(ref source_root) => source_root.join(source_file),
+            None => PathBuf::from(source_file)
+        };
+        fs::read(path).ok().map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+    }
+    fn index(&self, pages: &BTreeMap<&str, String>) -> String {
+        let mut html = String::new();
+        let directories = self.report.directories();
+
+        html.push_str(&header(&self.title, &self.title));
+        html.push_str(&summary_table(&self.report.coverage()));
+        html.push_str("<table class=\"summary\">\n");
+        html.push_str(&coverage_heading("Directory / File"));
+        for directory in directories.walk().into_iter().filter(|directory| !directory.files().is_empty()) {
+            html.push_str(&coverage_row("directory", &escape(directory.path()), &directory.coverage()));
+            for (source_file, coverage) in directory.files().iter() {
+                let file_name = Path::new(source_file).file_name()
+                    .map_or_else(|| source_file.clone(), |name| name.to_string_lossy().into_owned());
+                let name = match pages.get(source_file.as_str()) {
+                    Some(page) => format!("<a href=\"{}\">{}</a>", escape(page), escape(&file_name)),
+                    None => escape(&file_name)
+                };
+                html.push_str(&coverage_row("file", &name, coverage));
+            }
+        }
+        html.push_str("</table>\n");
+        html.push_str(FOOTER);
+        html
+    }
+    fn file_page(&self, source_file: &str, file: &File, source: Option<&str>) -> String {
+        let test = file.flatten();
+        let mut html = String::new();
+
+        html.push_str(&header(&self.title, &format!("{} - {}", self.title, source_file)));
+        let _ = writeln!(html, "<p><a href=\"../index.html\">{}</a> / {}</p>", escape(&self.title), escape(source_file));
+        html.push_str(&summary_table(&CoverageSummary::of_test(&test)));
+
+        if !test.functions().is_empty() {
+            html.push_str("<h2>Functions</h2>\n<table class=\"summary\">\n");
+            html.push_str("<tr><th class=\"name\">Function</th><th>Line</th><th>Hits</th></tr>\n");
+            for (name, function) in test.functions().iter() {
+                let class = if function.is_hit() { "high" } else { "low" };
+                let _ = writeln!(html, "<tr><td class=\"name\"><a href=\"#L{}\">{}</a></td><td>{}</td><td class=\"{}\">{}</td></tr>",
+                    function.line_number(), escape(name), function.line_number(), class, function.execution_count());
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Source</h2>\n");
+        let rows: Vec<(LineNumber, &str)> = match source {
+            Some(source) => source.lines().enumerate().map(|(index, text)| (index as LineNumber + 1, text)).collect(),
+            None => {
+                html.push_str("<p>The source file is not available, only the covered lines are listed.</p>\n");
+                test.lines().iter().map(|(line_number, _)| (*line_number, "")).collect()
+            }
+        };
+        html.push_str(&source_table(&test, &rows));
+        html.push_str(FOOTER);
+        html
+    }
+}
+
+const FOOTER: &'static str = "</body>\n</html>\n";
+
+fn write_file(path: &Path, content: &str) -> IOResult<()> {
+    let mut file = try!(OutputFile::create(path));
+    file.write_all(content.as_bytes())
+}
+
+fn header(title: &str, page_title: &str) -> String {
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(page_title), STYLE, escape(title))
+}
+
+fn level(coverage: &Coverage) -> &'static str {
+    match coverage.percentage() {
+        Some(percentage) if percentage >= 90.0 => "high",
+        Some(percentage) if percentage >= 75.0 => "medium",
+        Some(_) => "low",
+        None => ""
+    }
+}
+
+fn coverage_cells(coverage: &Coverage) -> String {
+    match coverage.percentage() {
+        Some(percentage) => format!("<td class=\"{}\">{:.1}%</td><td>{} / {}</td>",
+            level(coverage), percentage, coverage.hit, coverage.found),
+        None => "<td>-</td><td>0 / 0</td>".to_string()
+    }
+}
+
+fn coverage_heading(name: &str) -> String {
+    format!("<tr><th class=\"name\">{}</th><th colspan=\"2\">Lines</th><th colspan=\"2\">Functions</th><th colspan=\"2\">Branches</th></tr>\n", name)
+}
+
+fn coverage_row(class: &str, name: &str, summary: &CoverageSummary) -> String {
+    format!("<tr class=\"{}\"><td class=\"name\">{}</td>{}{}{}</tr>\n", class, name,
+        coverage_cells(&summary.lines), coverage_cells(&summary.functions), coverage_cells(&summary.branches))
+}
+
+fn summary_table(summary: &CoverageSummary) -> String {
+    let mut html = String::from("<table class=\"summary\">\n");
+    html.push_str(&coverage_heading(""));
+    html.push_str(&coverage_row("total", "Total", summary));
+    html.push_str("</table>\n");
+    html
+}
+
+/// Branch markers of the line: `+` taken, `-` not taken, `#` never evaluated
+fn branch_markers(test: &Test, line_number: LineNumber) -> String {
+    let blocks = match test.branches().get(&line_number) {
+        Some(blocks) => blocks,
+        None => return String::new()
+    };
+    blocks.iter().map(|(unit, branch)| {
+        let (class, marker) = match branch.execution_count() {
+            Some(_) if branch.is_hit() => ("taken", "+"),
+            Some(_) => ("not-taken", "-"),
+            None => ("not-evaluated", "#")
+        };
+        format!("<span class=\"{}\" title=\"branch {}\">{}</span>", class, unit, marker)
+    }).collect::<Vec<String>>().join("")
+}
+
+fn source_table(test: &Test, rows: &[(LineNumber, &str)]) -> String {
+    let mut html = String::from("<table class=\"source\">\n");
+    for &(line_number, text) in rows.iter() {
+        let (class, count) = match test.lines().get(&line_number) {
+            Some(line) if line.is_hit() => ("hit", line.execution_count().to_string()),
+            Some(line) => ("miss", line.execution_count().to_string()),
+            None => ("", String::new())
+        };
+        let _ = writeln!(html,
+            "<tr id=\"L{}\" class=\"{}\"><td class=\"line-number\">{}</td><td class=\"branches\">{}</td><td class=\"count\">{}</td><td class=\"source\">{}</td></tr>",
+            line_number, class, line_number, branch_markers(test, line_number), count, escape(text));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::Cursor;
+    use merger::*;
+
+    #[test]
+    fn write_to() {
+        let input = concat!(
+            "TN:\nSF:tests/fixtures/fixture.c\nFN:4,main\nFNDA:1,main\n",
+            "BRDA:6,0,0,1\nBRDA:6,0,1,0\nBRDA:7,0,0,-\nDA:6,1\nDA:7,0\nend_of_record\n",
+            "TN:\nSF:tests/fixtures/missing.c\nDA:2,1\nend_of_record\n"
+        );
+        let report = merge_readers(vec![Cursor::new(input.as_bytes())]).unwrap();
+        let output_dir = env::temp_dir().join("lcov-merge-html");
+        let _ = fs::remove_dir_all(&output_dir);
+
+        report.html().source_root(".").title("fixtures").write_to(&output_dir).unwrap();
+
+        let index = fs::read_to_string(output_dir.join("index.html")).unwrap();
+        assert!(index.contains("<a href=\"files/1-tests_fixtures_fixture.c.html\">fixture.c</a>"));
+        assert!(index.contains("<td class=\"name\">tests/fixtures</td><td class=\"low\">66.7%</td><td>2 / 3</td>"));
+        assert!(!index.contains("http"));
+
+        let page = fs::read_to_string(output_dir.join("files/1-tests_fixtures_fixture.c.html")).unwrap();
+        assert!(page.contains("<a href=\"#L4\">main</a>"));
+        assert!(page.contains("<tr id=\"L6\" class=\"hit\"><td class=\"line-number\">6</td><td class=\"branches\"><span class=\"taken\" title=\"branch 0-0\">+</span><span class=\"not-taken\" title=\"branch 0-1\">-</span></td><td class=\"count\">1</td><td class=\"source\">  func1(0, 2);</td></tr>"));
+        assert!(page.contains("<tr id=\"L7\" class=\"miss\">"));
+        assert!(page.contains("<span class=\"not-evaluated\" title=\"branch 0-0\">#</span>"));
+
+        let missing = fs::read_to_string(output_dir.join("files/2-tests_fixtures_missing.c.html")).unwrap();
+        assert!(missing.contains("The source file is not available"));
+        assert!(missing.contains("<tr id=\"L2\" class=\"hit\">"));
+    }
+
+    #[test]
+    fn index_of_unnormalized_paths() {
+        let input = "TN:\nSF:src/./main.c\nDA:1,1\nend_of_record\nTN:\nSF:src//lib.c\nDA:1,0\nend_of_record\nTN:\nSF:src/\nDA:1,1\nend_of_record\n";
+        let report = merge_readers(vec![Cursor::new(input.as_bytes())]).unwrap();
+        let html = report.html();
+        let index = html.index(&html.pages());
+
+        assert!(index.contains("<a href=\"files/1-src.html\">src</a>"));
+        assert!(index.contains("<a href=\"files/2-src_._main.c.html\">main.c</a>"));
+        assert!(index.contains("<a href=\"files/3-src__lib.c.html\">lib.c</a>"));
+    }
+}
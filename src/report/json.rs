@@ -0,0 +1,205 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::fmt:: { Display, Formatter, Result as FormatResult };
+use std::io:: { Read };
+use serde:: { Serialize, Deserialize };
+use serde_json:: { Error as JsonError, Value };
+use report:: { Report };
+use report::test:: { Test };
+use report::summary:: { Summary };
+use report::attribute:: { LineNumber, ExecutionCount, CheckSum, FunctionName, SourceFile, TestName };
+
+/// Version of the JSON representation written by `Report::json`
+pub const JSON_VERSION: u32 = 1;
+
+/// The JSON representation of a report
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "files": {
+///     "/src/main.c": {
+///       "tests": {
+///         "": {
+///           "lines": [{ "line": 1, "count": 2, "checksum": null }],
+///           "functions": [{ "name": "main", "line": 1, "end_line": null, "count": 2 }],
+///           "branches": [{ "line": 2, "block": 0, "branch": 0, "taken": null }]
+///         }
+///       }
+///     }
+///   }
+/// }
+/// ```
+///
+/// A `taken` of `null` is a branch that was never evaluated (`-` in LCOV).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct JsonReport {
+    pub version: u32,
+    pub files: BTreeMap<SourceFile, JsonFile>
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct JsonFile {
+    pub tests: BTreeMap<TestName, JsonTest>
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct JsonTest {
+    #[serde(default)]
+    pub lines: Vec<JsonLine>,
+    #[serde(default)]
+    pub functions: Vec<JsonFunction>,
+    #[serde(default)]
+    pub branches: Vec<JsonBranch>
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct JsonLine {
+    pub line: LineNumber,
+    pub count: ExecutionCount,
+    #[serde(default)]
+    pub checksum: Option<CheckSum>
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct JsonFunction {
+    pub name: FunctionName,
+    pub line: LineNumber,
+    #[serde(default)]
+    pub end_line: Option<LineNumber>,
+    pub count: ExecutionCount
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct JsonBranch {
+    pub line: LineNumber,
+    pub block: u32,
+    pub branch: u32,
+    pub taken: Option<ExecutionCount>
+}
+
+impl JsonReport {
+    /// Read the JSON representation, failing on a version other than `JSON_VERSION`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, JsonError> {
+        let report: JsonReport = try!(serde_json::from_reader(reader));
+        report.checked()
+    }
+    /// Read the JSON representation from a parsed value, failing on a version other than `JSON_VERSION`.
+    pub fn from_value(value: Value) -> Result<Self, JsonError> {
+        let report: JsonReport = try!(serde_json::from_value(value));
+        report.checked()
+    }
+    fn checked(self) -> Result<Self, JsonError> {
+        if self.version != JSON_VERSION {
+            let message = format!("unsupported version {}, expected {}", self.version, JSON_VERSION);
+            return Err(serde::de::Error::custom(message));
+        }
+        Ok(self)
+    }
+}
+
+impl<'a> From<&'a Report> for JsonReport {
+    fn from(report: &'a Report) -> Self {
+        let files = report.files().iter().map(|(source_file, file)| {
+            let tests = file.tests().iter()
+                .map(|(test_name, test)| (test_name.clone(), JsonTest::from(test)))
+                .collect();
+            (source_file.clone(), JsonFile { tests: tests })
+        }).collect();
+
+        JsonReport {
+            version: JSON_VERSION,
+            files: files
+        }
+    }
+}
+
+impl<'a> From<&'a Test> for JsonTest {
+    fn from(test: &'a Test) -> Self {
+        let lines = test.lines().iter().map(|(_, line)| {
+            JsonLine {
+                line: *line.line_number(),
+                count: *line.execution_count(),
+                checksum: line.checksum().cloned()
+            }
+        }).collect();
+        let functions = test.functions().iter().map(|(_, function)| {
+            JsonFunction {
+                name: function.name().clone(),
+                line: *function.line_number(),
+                end_line: function.end_line_number().cloned(),
+                count: *function.execution_count()
+            }
+        }).collect();
+        let branches = test.branches().iter()
+            .flat_map(|(_, blocks)| blocks.iter())
+            .map(|(_, branch)| {
+                JsonBranch {
+                    line: *branch.line_number(),
+                    block: *branch.block(),
+                    branch: *branch.branch(),
+                    taken: branch.execution_count().cloned()
+                }
+            }).collect();
+
+        JsonTest {
+            lines: lines,
+            functions: functions,
+            branches: branches
+        }
+    }
+}
+
+/// The report as JSON
+pub struct Json<'a> {
+    report: &'a Report
+}
+
+impl<'a> Json<'a> {
+    pub fn new(report: &'a Report) -> Self {
+        Json {
+            report: report
+        }
+    }
+}
+
+impl<'a> Display for Json<'a> {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        let json = try!(serde_json::to_string_pretty(&JsonReport::from(self.report)).map_err(|_| ::std::fmt::Error));
+        writeln!(f, "{}", json)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use merger::*;
+    use report::json:: { JsonReport };
+
+    #[test]
+    fn roundtrip() {
+        let report = merge_files(&["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let json = report.json().to_string();
+        let imported = merge_readers(vec![Cursor::new(json.as_bytes())]).unwrap();
+
+        assert_eq!(imported.to_string(), report.to_string());
+        assert_eq!(JsonReport::from_reader(json.as_bytes()).unwrap(), JsonReport::from(&report));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let error = JsonReport::from_reader(r#"{ "version": 2, "files": {} }"#.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("unsupported version 2"));
+    }
+
+    #[test]
+    fn not_evaluated_branch() {
+        let json = r#"{ "version": 1, "files": { "/src/main.c": { "tests": { "": {
+            "lines": [{ "line": 2, "count": 1 }],
+            "branches": [{ "line": 2, "block": 0, "branch": 0, "taken": null }, { "line": 2, "block": 0, "branch": 1, "taken": 3 }]
+        } } } } }"#;
+        let report = merge_readers(vec![Cursor::new(json.as_bytes())]).unwrap();
+
+        assert_eq!(report.to_string(), "TN:\nSF:/src/main.c\nBRDA:2,0,0,-\nBRDA:2,0,1,3\nBRF:2\nBRH:1\nDA:2,1\nLF:1\nLH:1\nend_of_record\n");
+    }
+}
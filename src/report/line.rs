@@ -8,7 +8,7 @@ use record:: { RecordWrite };
 use report::summary:: { Summary };
 use report::attribute:: { LineNumber, CheckSum, ExecutionCount };
 use report::counter:: { Hit, HitFoundCounter, FoundCounter, HitCounter };
-use merger::ops:: { TryMerge, MergeResult, MergeLine, ChecksumError };
+use merger::ops:: { Merge, TryMerge, MergeResult, MergeLine, ChecksumError };
 
 #[derive(Debug, Eq, Clone)]
 pub struct Line {
@@ -41,6 +41,9 @@ impl Line {
             None => None
         }
     }
+    pub fn set_checksum(&mut self, checksum: Option<CheckSum>) {
+        self.checksum = checksum;
+    }
     pub fn has_checkshum(&self) -> bool {
         self.checksum.is_some()
     }
@@ -73,17 +76,20 @@ impl<'a> TryMerge<&'a Line> for Line {
     type Err = ChecksumError;
 
     fn try_merge(&mut self, other: &'a Line) -> MergeResult<Self::Err> {
-        if !other.has_checkshum() {
-            return Err(ChecksumError::Empty(MergeLine::from(other)));
-        }
-
-        if self.checksum.as_ref() != other.checksum() {
-            return Err(ChecksumError::Mismatch(
-                MergeLine::from(&self.clone()),
-                MergeLine::from(other)
-            ));
+        match (self.has_checkshum(), other.has_checkshum()) {
+            (false, false) => {},
+            (true, true) => {
+                if self.checksum.as_ref() != other.checksum() {
+                    return Err(ChecksumError::Mismatch(
+                        MergeLine::from(&self.clone()),
+                        MergeLine::from(other)
+                    ));
+                }
+            },
+            (true, false) => return Err(ChecksumError::Empty(MergeLine::from(other))),
+            (false, true) => return Err(ChecksumError::Empty(MergeLine::from(&self.clone())))
         }
-        self.execution_count += *other.execution_count();
+        self.execution_count = self.execution_count.saturating_add(*other.execution_count());
         Ok(())
     }
 }
@@ -117,6 +123,7 @@ impl AsRef<BTreeMap<LineNumber, Line>> for Lines {
 }
 
 impl_summary!(Lines, lines<LineNumber, Line>);
+impl_summary_mut!(Lines, lines<LineNumber, Line>);
 
 
 impl HitCounter for Lines {
@@ -178,6 +185,15 @@ impl<'a> TryMerge<&'a LineData> for Lines {
 
 impl_try_merge_self_summary!(Lines:lines, ChecksumError);
 
+/// Sum up the execution counts by line number, without the checksums.
+impl<'a> Merge<&'a Line> for Lines {
+    fn merge(&mut self, line: &'a Line) {
+        let merged = self.lines.entry(line.line_number)
+            .or_insert_with(|| Line::new(line.line_number, 0, None));
+        merged.execution_count = merged.execution_count.saturating_add(line.execution_count);
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -208,6 +224,16 @@ mod tests {
         assert_eq!( lines.get(&1), Some(&Line::new(1, 2, Some("abc".to_string()))) );
     }
 
+    #[test]
+    fn add_line_data_without_checksum() {
+        let mut lines = Lines::new();
+        lines.try_merge(&LineData { line: 1, count: 1, checksum: None }).unwrap();
+        lines.try_merge(&LineData { line: 1, count: 2, checksum: None }).unwrap();
+
+        assert_eq!( lines.get(&1).map(|line| *line.execution_count()), Some(3) );
+        assert!( lines.try_merge(&LineData { line: 1, count: 1, checksum: Some("abc".to_string()) }).is_err() );
+    }
+
     #[test]
     fn hit_count_and_found_count() {
         let mut lines = Lines::new();
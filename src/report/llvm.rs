@@ -0,0 +1,288 @@
+use std::collections::btree_map:: { BTreeMap };
+use std::convert:: { TryFrom };
+use std::io:: { Read };
+use serde:: { Deserialize };
+use serde_json:: { Error as JsonError, Value };
+use lcov_parser:: { LineData };
+use report::branch:: { Branch };
+use report::function:: { Function };
+use report::attribute:: { LineNumber, execution_count };
+
+/// `type` of the JSON written by `llvm-cov export -format=text`
+pub const LLVM_EXPORT_TYPE: &str = "llvm.coverage.json.export";
+
+/// The JSON written by `llvm-cov export -format=text`
+///
+/// Only the segments and branches of the files and the functions are read;
+/// the summaries, expansions and MC/DC records are ignored.
+///
+/// ```json
+/// {
+///   "type": "llvm.coverage.json.export",
+///   "version": "2.0.1",
+///   "data": [{
+///     "files": [{
+///       "filename": "/src/main.rs",
+///       "segments": [[1, 11, 1, true, true, false], [3, 2, 0, false, false, false]],
+///       "branches": [[2, 8, 2, 12, 1, 0, 0, 0, 4]]
+///     }],
+///     "functions": [{ "name": "_RNvCs1_4main4main", "count": 1, "regions": [[1, 11, 3, 2, 1, 0, 0, 0]], "filenames": ["/src/main.rs"] }]
+///   }]
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct LlvmReport {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub version: String,
+    pub data: Vec<LlvmExport>
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct LlvmExport {
+    #[serde(default)]
+    pub files: Vec<LlvmFile>,
+    #[serde(default)]
+    pub functions: Vec<LlvmFunction>
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct LlvmFile {
+    pub filename: String,
+    #[serde(default)]
+    pub segments: Vec<LlvmSegment>,
+    #[serde(default)]
+    pub branches: Vec<LlvmBranch>
+}
+
+/// `[line, column, count, has count, is region entry, is gap region]`,
+/// where the gap region flag is missing before LLVM 11
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(try_from = "Vec<Value>")]
+pub struct LlvmSegment {
+    pub line: LineNumber,
+    pub column: u32,
+    pub count: u64,
+    pub has_count: bool,
+    pub is_region_entry: bool,
+    pub is_gap_region: bool
+}
+
+/// `[line start, column start, line end, column end, true count, false count, file, expanded file, kind]`
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(try_from = "Vec<Value>")]
+pub struct LlvmBranch {
+    pub line: LineNumber,
+    pub true_count: u64,
+    pub false_count: u64
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct LlvmFunction {
+    pub name: String,
+    pub count: u64,
+    /// `[line start, column start, line end, column end, count, file, expanded file, kind]`
+    #[serde(default)]
+    pub regions: Vec<Vec<u64>>,
+    #[serde(default)]
+    pub filenames: Vec<String>
+}
+
+impl TryFrom<Vec<Value>> for LlvmSegment {
+    type Error = String;
+
+    fn try_from(values: Vec<Value>) -> Result<Self, Self::Error> {
+        if values.len() < 5 {
+            return Err(format!("a segment expects at least 5 values, got {}", values.len()));
+        }
+        Ok(LlvmSegment {
+            line: try!(number(&values[0])) as LineNumber,
+            column: try!(number(&values[1])) as u32,
+            count: try!(number(&values[2])),
+            has_count: try!(flag(&values[3])),
+            is_region_entry: try!(flag(&values[4])),
+            is_gap_region: match values.get(5) {
+                Some(value) => try!(flag(value)),
+                None => false
+            }
+        })
+    }
+}
+
+impl TryFrom<Vec<Value>> for LlvmBranch {
+    type Error = String;
+
+    fn try_from(values: Vec<Value>) -> Result<Self, Self::Error> {
+        if values.len() < 6 {
+            return Err(format!("a branch expects at least 6 values, got {}", values.len()));
+        }
+        Ok(LlvmBranch {
+            line: try!(number(&values[0])) as LineNumber,
+            true_count: try!(number(&values[4])),
+            false_count: try!(number(&values[5]))
+        })
+    }
+}
+
+fn number(value: &Value) -> Result<u64, String> {
+    value.as_u64().ok_or_else(|| format!("expected a number, got {}", value))
+}
+
+/// Flags are booleans, or 0 and 1 in the older exports.
+fn flag(value: &Value) -> Result<bool, String> {
+    match *value {
+        Value::Bool(flag) => Ok(flag),
+        Value::Number(ref number) if number.as_u64() == Some(0) || number.as_u64() == Some(1) => {
+            Ok(number.as_u64() == Some(1))
+        },
+        _ => Err(format!("expected a boolean, got {}", value))
+    }
+}
+
+impl LlvmReport {
+    /// Read the export, failing on a `type` other than `LLVM_EXPORT_TYPE`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, JsonError> {
+        let report: LlvmReport = try!(serde_json::from_reader(reader));
+        report.checked()
+    }
+    /// Read the export from a parsed value, failing on a `type` other than `LLVM_EXPORT_TYPE`.
+    pub fn from_value(value: Value) -> Result<Self, JsonError> {
+        let report: LlvmReport = try!(serde_json::from_value(value));
+        report.checked()
+    }
+    fn checked(self) -> Result<Self, JsonError> {
+        if self.kind != LLVM_EXPORT_TYPE {
+            let message = format!("unsupported type {}, expected {}", self.kind, LLVM_EXPORT_TYPE);
+            return Err(serde::de::Error::custom(message));
+        }
+        Ok(self)
+    }
+}
+
+impl LlvmExport {
+    /// Functions defined in the file
+    pub fn functions_of(&self, file: &LlvmFile) -> Vec<Function> {
+        self.functions.iter()
+            .filter(|function| function.filenames.first() == Some(&file.filename))
+            .filter_map(|function| function.to_function())
+            .collect()
+    }
+}
+
+impl LlvmFunction {
+    /// The function spanning its first region, `None` for a function without regions
+    pub fn to_function(&self) -> Option<Function> {
+        let region = self.regions.first().filter(|region| region.len() >= 3)?;
+        let function = Function::new(self.name.clone(), region[0] as LineNumber, execution_count(self.count))
+            .with_end_line_number(Some(region[2] as LineNumber));
+        Some(function)
+    }
+}
+
+impl LlvmFile {
+    /// Lines of the file, counted from the segments like `llvm-cov export -format=lcov` does
+    ///
+    /// A line is counted by the segment wrapping it from the previous lines and the regions starting on it,
+    /// and has no count when it is skipped or outside of any region with a count.
+    pub fn to_lines(&self) -> Vec<LineData> {
+        let mut lines = vec![];
+        let last_line = match self.segments.last() {
+            Some(segment) => segment.line,
+            None => return lines
+        };
+        let mut wrapped: Option<&LlvmSegment> = None;
+        let mut next = 0;
+
+        for line_number in self.segments[0].line..last_line + 1 {
+            let start = next;
+            while next < self.segments.len() && self.segments[next].line == line_number {
+                next += 1;
+            }
+            let starts = &self.segments[start..next];
+            if let Some(count) = line_count(wrapped, starts) {
+                lines.push(LineData { line: line_number, count: execution_count(count), checksum: None });
+            }
+            if let Some(segment) = starts.last() {
+                wrapped = Some(segment);
+            }
+        }
+        lines
+    }
+    /// Branches of the file, a block per branch region of the line with its true (0) and false (1) branches
+    ///
+    /// The branches of a line that was never executed were never evaluated.
+    pub fn to_branches(&self) -> Vec<Branch> {
+        let executed: BTreeMap<LineNumber, u32> = self.to_lines().iter()
+            .map(|line| (line.line, line.count))
+            .collect();
+        let mut blocks = BTreeMap::new();
+        let mut branches = vec![];
+
+        for branch in self.branches.iter() {
+            let block = blocks.entry(branch.line).or_insert(0);
+            let counts = [branch.true_count, branch.false_count];
+            for (index, &count) in counts.iter().enumerate() {
+                branches.push(match executed.get(&branch.line) {
+                    Some(&line_count) if line_count > 0 => Branch::new(branch.line, *block, index as u32, execution_count(count)),
+                    _ => Branch::not_evaluated(branch.line, *block, index as u32)
+                });
+            }
+            *block += 1;
+        }
+        branches
+    }
+}
+
+fn is_region_start(segment: &LlvmSegment) -> bool {
+    !segment.is_gap_region && segment.has_count && segment.is_region_entry
+}
+
+/// Count of a line from the segment wrapping it and the segments starting on it, `None` when unmapped
+fn line_count(wrapped: Option<&LlvmSegment>, starts: &[LlvmSegment]) -> Option<u64> {
+    let skipped = starts.first().is_some_and(|segment| !segment.has_count && segment.is_region_entry);
+    let region_starts: Vec<&LlvmSegment> = starts.iter().filter(|segment| is_region_start(segment)).collect();
+    let mapped = !skipped && (wrapped.is_some_and(|segment| segment.has_count) || !region_starts.is_empty());
+    if !mapped {
+        return None;
+    }
+    let count = wrapped.map_or(0, |segment| segment.count);
+    Some(region_starts.iter().fold(count, |count, segment| count.max(segment.count)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use report::llvm:: { LlvmReport };
+
+    #[test]
+    fn skipped_region() {
+        let json = r#"{ "type": "llvm.coverage.json.export", "version": "2.0.1", "data": [{ "files": [{
+            "filename": "/src/lib.c",
+            "segments": [[1, 1, 3, 1, 1], [2, 1, 0, 0, 1], [2, 10, 3, 1, 0], [4, 2, 0, 0, 0]],
+            "branches": [[1, 5, 1, 9, 2, 1, 0, 0, 4], [1, 12, 1, 16, 0, 3, 0, 0, 4], [2, 5, 2, 9, 0, 0, 0, 0, 4]]
+        }] }] }"#;
+        let report = LlvmReport::from_reader(json.as_bytes()).unwrap();
+        let file = &report.data[0].files[0];
+
+        let lines: Vec<(u32, u32)> = file.to_lines().iter().map(|line| (line.line, line.count)).collect();
+        assert_eq!(lines, vec![(1, 3), (3, 3), (4, 3)]);
+        let branches: Vec<(u32, u32, u32, Option<u32>)> = file.to_branches().iter()
+            .map(|branch| (*branch.line_number(), *branch.block(), *branch.branch(), branch.execution_count().cloned()))
+            .collect();
+        assert_eq!(branches, vec![
+            (1, 0, 0, Some(2)),
+            (1, 0, 1, Some(1)),
+            (1, 1, 0, Some(0)),
+            (1, 1, 1, Some(3)),
+            (2, 0, 0, None),
+            (2, 0, 1, None)
+        ]);
+    }
+
+    #[test]
+    fn unsupported_type() {
+        let json = r#"{ "type": "llvm.coverage.json.summary", "version": "2.0.1", "data": [] }"#;
+        let error = LlvmReport::from_reader(json.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("unsupported type llvm.coverage.json.summary"));
+    }
+}
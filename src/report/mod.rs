@@ -1,12 +1,26 @@
 use std::fmt;
-use std::fs:: { OpenOptions, File as OutputFile };
+use std::fs:: { OpenOptions };
 use std::convert::{ AsRef };
 use std::io:: { Result as IOResult };
 use std::io::prelude::*;
-use std::path::Path;
-use report::summary:: { Summary };
+use std::path:: { Path, PathBuf };
+use report::summary:: { Summary, SummaryMut };
 use report::file:: { File, Files };
+use report::diagnostic:: { Diagnostic };
+use report::exclusion:: { Exclusions };
+use report::coverage:: { CoverageSummary };
+use report::directory:: { Directory };
+use report::cobertura:: { Cobertura };
+use report::json:: { Json };
+use report::sonar:: { Sonar };
+use report::codecov:: { Codecov };
+use report::coveralls:: { Coveralls };
+use report::html:: { Html };
+use report::function:: { FunctionFormat };
+use report::conflict:: { Conflict };
+use merger::filter:: { SourceFilter };
 use record:: { RecordWrite };
+use compression:: { Compression, Encoder };
 
 pub mod attribute;
 pub mod summary;
@@ -16,17 +30,44 @@ pub mod line;
 pub mod function;
 pub mod test;
 pub mod counter;
+pub mod diagnostic;
+pub mod conflict;
+pub mod exclusion;
+pub mod coverage;
+pub mod directory;
+pub mod threshold;
+pub mod cobertura;
+pub mod json;
+pub mod sonar;
+pub mod codecov;
+pub mod coveralls;
+pub mod gcov;
+pub mod llvm;
+pub mod html;
+mod xml;
 
 pub struct Report {
-    files: Files
+    files: Files,
+    diagnostics: Vec<Diagnostic>,
+    conflicts: Vec<Conflict>
 }
 
 impl Report {
     pub fn new(files: Files) -> Self {
         Report {
-            files: files
+            files: files,
+            diagnostics: vec![],
+            conflicts: vec![]
         }
     }
+    pub fn with_diagnostics(mut self, diagnostics: Vec<Diagnostic>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+    pub fn with_conflicts(mut self, conflicts: Vec<Conflict>) -> Self {
+        self.conflicts = conflicts;
+        self
+    }
     pub fn get(&self, key: &str) -> Option<&File> {
         self.files.get(&key.to_string())
     }
@@ -36,25 +77,141 @@ impl Report {
     pub fn len(&self) -> usize {
         self.files.len()
     }
+    /// Coverage over all files of the report
+    pub fn coverage(&self) -> CoverageSummary {
+        let mut summary = CoverageSummary::new();
+        for (_, file) in self.files.iter() {
+            summary += file.coverage();
+        }
+        summary
+    }
+    /// Coverage of the source files rolled up per directory
+    pub fn directories(&self) -> Directory {
+        Directory::from_files(&self.files)
+    }
+    /// The report as Cobertura XML
+    pub fn cobertura(&self) -> Cobertura<'_> {
+        Cobertura::new(self)
+    }
+    /// The report as JSON, which can be merged again with `ReportMerger`
+    pub fn json(&self) -> Json<'_> {
+        Json::new(self)
+    }
+    /// The report as SonarQube generic test coverage XML
+    pub fn sonar(&self) -> Sonar<'_> {
+        Sonar::new(self)
+    }
+    /// The report as a Codecov JSON payload
+    pub fn codecov(&self) -> Codecov<'_> {
+        Codecov::new(self)
+    }
+    /// The report as a Coveralls JSON payload
+    pub fn coveralls(&self) -> Coveralls<'_> {
+        Coveralls::new(self)
+    }
+    /// The report as offline HTML pages with the annotated source files
+    pub fn html(&self) -> Html<'_> {
+        Html::new(self)
+    }
+    /// Problems skipped while merging in lenient mode
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    /// Checksum conflicts resolved by the `ConflictPolicy` while merging
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+    /// Report of the source files selected by the filter
+    pub fn filter(&self, filter: &SourceFilter) -> Report {
+        let mut files = self.files.clone();
+        files.retain(|source_file, _| filter.matches(source_file));
+        Report::new(files)
+            .with_diagnostics(self.diagnostics.clone())
+            .with_conflicts(self.conflicts.clone())
+    }
+    /// Report without the lines, functions and branches excluded by the `LCOV_EXCL_*` markers
+    ///
+    /// The source files are read from their paths, the relative ones under the root when given.
+    /// A source file that cannot be read is kept as it is, and reported by `Report::diagnostics`.
+    pub fn exclude_marked(&self, root: Option<&Path>) -> Report {
+        let mut files = self.files.clone();
+        let mut diagnostics = self.diagnostics.clone();
+        for (source_file, file) in files.iter_mut() {
+            let path = match root {
+                Some(root) => root.join(source_file),
+                None => PathBuf::from(source_file)
+            };
+            let exclusions = match Exclusions::from_path(path) {
+                Ok(exclusions) => exclusions,
+                Err(err) => {
+                    let reason = format!("exclusion markers are not applied: {}", err);
+                    diagnostics.push(Diagnostic::new(source_file.clone(), 0, reason));
+                    continue;
+                }
+            };
+            if exclusions.is_empty() {
+                continue;
+            }
+            for (_, test) in file.tests_mut().iter_mut() {
+                test.exclude(&exclusions);
+            }
+        }
+        Report::new(files)
+            .with_diagnostics(diagnostics)
+            .with_conflicts(self.conflicts.clone())
+    }
+    /// Records of the report, writing the functions in the syntax of the format
+    pub fn records(&self, format: FunctionFormat) -> ReportRecords<'_> {
+        ReportRecords {
+            report: self,
+            format: format
+        }
+    }
+    /// Write the report to the file, compressed when the path ends with `.gz` or `.zst`.
     pub fn save_as<T: AsRef<Path>>(&self, path: T) -> IOResult<()> {
-        let mut output = try!(OpenOptions::new().create(true).write(true).open(path));
-        self.write_records::<OutputFile>(&mut output)
+        self.save_as_with(path, FunctionFormat::Legacy)
+    }
+    /// Write the report to the file like `save_as`, writing the functions in the syntax of the format.
+    pub fn save_as_with<T: AsRef<Path>>(&self, path: T, format: FunctionFormat) -> IOResult<()> {
+        let compression = Compression::from_path(path.as_ref());
+        let output = try!(OpenOptions::new().create(true).write(true).truncate(true).open(path));
+        let mut encoder = try!(Encoder::new(output, compression));
+        try!(self.records(format).write_records(&mut encoder));
+        try!(encoder.finish());
+        Ok(())
     }
 }
 
 impl RecordWrite for Report {
     fn write_records<T: Write>(&self, output: &mut T) -> IOResult<()> {
-        writeln!(output, "{}", self)
+        self.records(FunctionFormat::Legacy).write_records(output)
     }
 }
 
 impl fmt::Display for Report {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (source_name, file) in self.files.iter() {
+        write!(f, "{}", self.records(FunctionFormat::Legacy))
+    }
+}
+
+pub struct ReportRecords<'a> {
+    report: &'a Report,
+    format: FunctionFormat
+}
+
+impl<'a> RecordWrite for ReportRecords<'a> {
+    fn write_records<T: Write>(&self, output: &mut T) -> IOResult<()> {
+        writeln!(output, "{}", self)
+    }
+}
+
+impl<'a> fmt::Display for ReportRecords<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (source_name, file) in self.report.files.iter() {
             for (test_name, test) in file.tests().iter() {
                 try!(writeln!(f, "TN:{}", test_name));
                 try!(writeln!(f, "SF:{}", source_name));
-                try!(write!(f, "{}", test.functions()));
+                try!(write!(f, "{}", test.functions().records(self.format)));
                 try!(write!(f, "{}", test.branches()));
                 try!(write!(f, "{}", test.lines()));
                 try!(writeln!(f, "end_of_record"));
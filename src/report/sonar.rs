@@ -0,0 +1,93 @@
+use std::fmt:: { Display, Formatter, Result };
+use report:: { Report };
+use report::summary:: { Summary };
+use report::counter:: { HitCounter, FoundCounter };
+use report::xml:: { escape };
+
+/// The report as SonarQube generic test coverage XML
+///
+/// The tests of a source file are summed up.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::merge_files;
+///
+/// let report = merge_files(&["tests/fixtures/fixture1.info"]).unwrap();
+/// let xml = report.sonar().to_string();
+///
+/// assert!(xml.starts_with(r#"<coverage version="1">"#));
+/// assert!(xml.contains(r#"<file path="/home/vagrant/shared/lcov-merge/tests/fixtures/func1.c">"#));
+/// ```
+pub struct Sonar<'a> {
+    report: &'a Report
+}
+
+impl<'a> Sonar<'a> {
+    pub fn new(report: &'a Report) -> Self {
+        Sonar {
+            report: report
+        }
+    }
+}
+
+impl<'a> Display for Sonar<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        try!(writeln!(f, r#"<coverage version="1">"#));
+        for (source_file, file) in self.report.files().iter() {
+            let test = file.flatten();
+            try!(writeln!(f, r#"  <file path="{}">"#, escape(source_file)));
+            for line_number in test.line_numbers().iter() {
+                let blocks = test.branches().get(line_number).filter(|blocks| !blocks.is_empty());
+                // A line with only branches is covered when one of its branches was taken.
+                let covered = match (test.lines().get(line_number), blocks) {
+                    (Some(line), _) => line.is_hit(),
+                    (None, Some(blocks)) => blocks.hit_count() > 0,
+                    (None, None) => continue
+                };
+                match blocks {
+                    Some(blocks) => {
+                        try!(writeln!(f, r#"    <lineToCover lineNumber="{}" covered="{}" branchesToCover="{}" coveredBranches="{}"/>"#,
+                            line_number, covered, blocks.found_count(), blocks.hit_count()));
+                    },
+                    None => {
+                        try!(writeln!(f, r#"    <lineToCover lineNumber="{}" covered="{}"/>"#, line_number, covered));
+                    }
+                }
+            }
+            try!(writeln!(f, "  </file>"));
+        }
+        writeln!(f, "</coverage>")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use merger::*;
+
+    #[test]
+    fn sonar() {
+        let input = concat!(
+            "TN:unit\nSF:/src/a&b.c\nBRDA:2,0,0,1\nBRDA:2,0,1,-\nDA:1,2\nDA:2,2\nDA:3,0\nend_of_record\n",
+            "TN:integration\nSF:/src/a&b.c\nBRDA:2,0,1,0\nDA:3,1\nend_of_record\n",
+            "TN:\nSF:/src/main.c\nBRDA:4,0,0,0\nBRDA:4,0,1,3\nBRDA:5,0,0,-\nDA:1,0\nend_of_record\n"
+        );
+        let report = merge_readers(vec![Cursor::new(input.as_bytes())]).unwrap();
+
+        assert_eq!(report.sonar().to_string(), r#"<coverage version="1">
+  <file path="/src/a&amp;b.c">
+    <lineToCover lineNumber="1" covered="true"/>
+    <lineToCover lineNumber="2" covered="true" branchesToCover="2" coveredBranches="1"/>
+    <lineToCover lineNumber="3" covered="true"/>
+  </file>
+  <file path="/src/main.c">
+    <lineToCover lineNumber="1" covered="false"/>
+    <lineToCover lineNumber="4" covered="true" branchesToCover="2" coveredBranches="1"/>
+    <lineToCover lineNumber="5" covered="false" branchesToCover="1" coveredBranches="0"/>
+  </file>
+</coverage>
+"#);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::btree_map:: { Iter };
+use std::collections::btree_map:: { Iter, IterMut };
 
 pub trait Summary<K, V> {
     fn iter(&self) -> Iter<K, V>;
@@ -9,3 +9,10 @@ pub trait Summary<K, V> {
         self.len() <= 0
     }
 }
+
+pub trait SummaryMut<K, V>: Summary<K, V> {
+    fn iter_mut(&mut self) -> IterMut<K, V>;
+    fn get_mut(&mut self, key: &K) -> Option<&mut V>;
+    fn remove(&mut self, key: &K) -> Option<V>;
+    fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F);
+}
@@ -1,12 +1,15 @@
 use std::default:: { Default };
 use std::collections::btree_map:: { BTreeMap };
+use std::collections::btree_set:: { BTreeSet };
 use lcov_parser:: { LineData, FunctionName, FunctionData, BranchData };
 use merger::ops:: { Merge, TryMerge, MergeResult, TestError, ChecksumError, FunctionError, BranchError };
-use report::attribute:: { TestName };
+use report::attribute:: { TestName, LineNumber };
 use report::line:: { Lines };
-use report::function:: { Functions };
-use report::branch:: { Branches };
-use report::summary:: { Summary };
+use report::function:: { Function, Functions };
+use report::branch:: { Branch, Branches };
+use report::summary:: { Summary, SummaryMut };
+use report::exclusion:: { Exclusions };
+use report::coverage:: { CoverageSummary };
 
 #[derive(Debug, Clone)]
 pub struct Test {
@@ -42,6 +45,32 @@ impl Test {
     pub fn branches(&self) -> &Branches {
         &self.branches
     }
+    pub fn lines_mut(&mut self) -> &mut Lines {
+        &mut self.lines
+    }
+    pub fn functions_mut(&mut self) -> &mut Functions {
+        &mut self.functions
+    }
+    pub fn branches_mut(&mut self) -> &mut Branches {
+        &mut self.branches
+    }
+    pub fn coverage(&self) -> CoverageSummary {
+        CoverageSummary::of_test(self)
+    }
+    /// Numbers of the lines with a `DA` record or with branches, some lines having only `BRDA` records
+    pub fn line_numbers(&self) -> BTreeSet<LineNumber> {
+        let lines = self.lines.iter().map(|(line_number, _)| *line_number);
+        let branch_lines = self.branches.iter()
+            .filter(|&(_, blocks)| !blocks.is_empty())
+            .map(|(line_number, _)| *line_number);
+        lines.chain(branch_lines).collect()
+    }
+    /// Drop the lines, functions and branches excluded by the markers of the source file.
+    pub fn exclude(&mut self, exclusions: &Exclusions) {
+        self.lines.retain(|line_number, _| !exclusions.is_line_excluded(line_number));
+        self.functions.retain(|_, function| !exclusions.is_line_excluded(function.line_number()));
+        self.branches.retain(|line_number, _| !exclusions.is_branch_excluded(line_number));
+    }
 }
 
 
@@ -77,6 +106,22 @@ impl<'a> TryMerge<&'a BranchData> for Test {
     }
 }
 
+impl<'a> TryMerge<&'a Function> for Test {
+    type Err = FunctionError;
+
+    fn try_merge(&mut self, function: &'a Function) -> MergeResult<Self::Err> {
+        self.functions.try_merge(function)
+    }
+}
+
+impl<'a> TryMerge<&'a Branch> for Test {
+    type Err = BranchError;
+
+    fn try_merge(&mut self, branch: &'a Branch) -> MergeResult<Self::Err> {
+        self.branches.try_merge(branch)
+    }
+}
+
 impl<'a> TryMerge<&'a Test> for Test {
     type Err = TestError;
 
@@ -113,6 +158,7 @@ impl Tests {
 }
 
 impl_summary!(Tests, tests<TestName, Test>);
+impl_summary_mut!(Tests, tests<TestName, Test>);
 
 
 impl<'a> Merge<&'a TestName> for Tests {
@@ -172,6 +218,30 @@ impl<'a> TryMerge<(&'a String, &'a BranchData)> for Tests {
     }
 }
 
+impl<'a> TryMerge<(&'a String, &'a Function)> for Tests {
+    type Err = FunctionError;
+
+    fn try_merge(&mut self, function: (&'a String, &'a Function)) -> MergeResult<Self::Err> {
+        if !self.tests.contains_key(function.0) {
+            self.tests.insert(function.0.clone(), Test::new());
+        }
+        let test = self.tests.get_mut(function.0).unwrap();
+        TryMerge::try_merge(test, function.1)
+    }
+}
+
+impl<'a> TryMerge<(&'a String, &'a Branch)> for Tests {
+    type Err = BranchError;
+
+    fn try_merge(&mut self, branch: (&'a String, &'a Branch)) -> MergeResult<Self::Err> {
+        if !self.tests.contains_key(branch.0) {
+            self.tests.insert(branch.0.clone(), Test::new());
+        }
+        let test = self.tests.get_mut(branch.0).unwrap();
+        TryMerge::try_merge(test, branch.1)
+    }
+}
+
 impl_try_merge_self_summary!(Tests:tests, TestError);
 
 
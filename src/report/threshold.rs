@@ -0,0 +1,270 @@
+use std::fmt:: { Display, Formatter, Result as FormatResult };
+use std::str:: { FromStr };
+use glob:: { Pattern, PatternError };
+use report:: { Report };
+use report::summary:: { Summary };
+use report::coverage:: { Coverage, CoverageSummary };
+
+/// Kind of coverage checked by a threshold
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Metric {
+    Lines,
+    Functions,
+    Branches
+}
+
+impl Metric {
+    pub fn coverage(&self, summary: &CoverageSummary) -> Coverage {
+        match *self {
+            Metric::Lines => summary.lines,
+            Metric::Functions => summary.functions,
+            Metric::Branches => summary.branches
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "lines" => Ok(Metric::Lines),
+            "functions" => Ok(Metric::Functions),
+            "branches" => Ok(Metric::Branches),
+            _ => Err(format!("unknown metric '{}'", value))
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            Metric::Lines => write!(f, "lines"),
+            Metric::Functions => write!(f, "functions"),
+            Metric::Branches => write!(f, "branches")
+        }
+    }
+}
+
+/// What a threshold was checked against
+#[derive(Debug, PartialEq, Clone)]
+pub enum Target {
+    /// All source files of the report
+    Total,
+    File(String),
+    Directory(String)
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            Target::Total => write!(f, "total"),
+            Target::File(ref path) => write!(f, "{}", path),
+            Target::Directory(ref path) => write!(f, "{}/", path)
+        }
+    }
+}
+
+/// A coverage below its required minimum
+#[derive(Debug, PartialEq, Clone)]
+pub struct Violation {
+    pub target: Target,
+    pub metric: Metric,
+    pub actual: f64,
+    pub required: f64
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        write!(f, "{}: {} coverage {:.1}% is below {:.1}%", self.target, self.metric, self.actual, self.required)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Scope {
+    Total,
+    Files(Pattern),
+    Directories(Pattern)
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    scope: Scope,
+    metric: Metric,
+    required: f64
+}
+
+/// Minimum coverage required of a report
+///
+/// Metrics without any found item (e.g. no branches) are never violated.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_merge::merge_files;
+/// use lcov_merge::threshold:: { Thresholds, Metric, Target };
+///
+/// let report = merge_files(&["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+/// let mut thresholds = Thresholds::new();
+/// thresholds
+///     .minimum(Metric::Lines, 90.0)
+///     .minimum(Metric::Branches, 60.0)
+///     .file_minimum("*/func2.c", Metric::Branches, 80.0).unwrap();
+///
+/// let violations = thresholds.check(&report);
+/// assert_eq!(violations.len(), 1);
+/// assert_eq!(violations[0].target, Target::File("/home/vagrant/shared/lcov-merge/tests/fixtures/func2.c".to_string()));
+/// assert_eq!(violations[0].metric, Metric::Branches);
+/// assert_eq!(violations[0].actual, 50.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Thresholds {
+    rules: Vec<Rule>
+}
+
+impl Thresholds {
+    pub fn new() -> Self {
+        Thresholds {
+            rules: vec![]
+        }
+    }
+    /// Require the minimum percentage of the metric over the whole report.
+    ///
+    /// The source files are not checked on their own, only against the file and directory minimums.
+    pub fn minimum(&mut self, metric: Metric, required: f64) -> &mut Self {
+        self.add(Scope::Total, metric, required)
+    }
+    /// Require the minimum percentage of the metric of every source file matching the glob pattern.
+    pub fn file_minimum(&mut self, pattern: &str, metric: Metric, required: f64) -> Result<&mut Self, PatternError> {
+        let pattern = try!(Pattern::new(pattern));
+        Ok(self.add(Scope::Files(pattern), metric, required))
+    }
+    /// Require the minimum percentage of the metric of every directory matching the glob pattern,
+    /// over the source files under the directory.
+    pub fn directory_minimum(&mut self, pattern: &str, metric: Metric, required: f64) -> Result<&mut Self, PatternError> {
+        let pattern = try!(Pattern::new(pattern));
+        Ok(self.add(Scope::Directories(pattern), metric, required))
+    }
+    fn add(&mut self, scope: Scope, metric: Metric, required: f64) -> &mut Self {
+        self.rules.push(Rule {
+            scope: scope,
+            metric: metric,
+            required: required
+        });
+        self
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+    /// Check the report, returning the violations of the total, then of the source files and of the directories.
+    pub fn check(&self, report: &Report) -> Vec<Violation> {
+        let mut violations = vec![];
+
+        for rule in self.rules.iter().filter(|rule| rule.is_total()) {
+            rule.check(Target::Total, &report.coverage(), &mut violations);
+        }
+        for (source_file, file) in report.files().iter() {
+            let coverage = file.coverage();
+            for rule in self.rules.iter() {
+                if let Scope::Files(ref pattern) = rule.scope {
+                    if pattern.matches(source_file) {
+                        rule.check(Target::File(source_file.clone()), &coverage, &mut violations);
+                    }
+                }
+            }
+        }
+        for directory in report.directories().walk().into_iter() {
+            for rule in self.rules.iter() {
+                if let Scope::Directories(ref pattern) = rule.scope {
+                    if !directory.path().is_empty() && pattern.matches(directory.path()) {
+                        let target = Target::Directory(directory.path().to_string());
+                        rule.check(target, &directory.coverage(), &mut violations);
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+impl Rule {
+    fn is_total(&self) -> bool {
+        matches!(self.scope, Scope::Total)
+    }
+    fn check(&self, target: Target, summary: &CoverageSummary, violations: &mut Vec<Violation>) {
+        let actual = match self.metric.coverage(summary).percentage() {
+            Some(actual) => actual,
+            None => return
+        };
+        if actual < self.required {
+            violations.push(Violation {
+                target: target,
+                metric: self.metric,
+                actual: actual,
+                required: self.required
+            });
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use merger::*;
+    use report::threshold:: { Thresholds, Metric, Target, Violation };
+
+    const FUNC1: &'static str = "/home/vagrant/shared/lcov-merge/tests/fixtures/func1.c";
+    const FUNC2: &'static str = "/home/vagrant/shared/lcov-merge/tests/fixtures/func2.c";
+    const FIXTURES: &'static str = "/home/vagrant/shared/lcov-merge/tests/fixtures";
+
+    #[test]
+    fn total() {
+        let report = merge_files(&["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let mut thresholds = Thresholds::new();
+        thresholds
+            .minimum(Metric::Lines, 95.0)
+            .minimum(Metric::Branches, 80.0);
+
+        assert_eq!(thresholds.check(&report), vec![
+            Violation { target: Target::Total, metric: Metric::Branches, actual: 75.0, required: 80.0 }
+        ]);
+    }
+
+    #[test]
+    fn total_passes_with_file_below_minimum() {
+        let report = merge_files(&["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let mut thresholds = Thresholds::new();
+        thresholds
+            .minimum(Metric::Lines, 90.0)
+            .minimum(Metric::Branches, 70.0);
+
+        assert!(thresholds.check(&report).is_empty());
+
+        thresholds.file_minimum("*/func2.c", Metric::Lines, 90.0).unwrap();
+        assert_eq!(thresholds.check(&report), vec![
+            Violation { target: Target::File(FUNC2.to_string()), metric: Metric::Lines, actual: 87.5, required: 90.0 }
+        ]);
+    }
+
+    #[test]
+    fn files_and_directories() {
+        let report = merge_files(&["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"]).unwrap();
+        let mut thresholds = Thresholds::new();
+        thresholds
+            .file_minimum("*/func1.c", Metric::Branches, 100.0).unwrap()
+            .file_minimum("*/fixture.c", Metric::Branches, 100.0).unwrap()
+            .directory_minimum("*/fixtures", Metric::Lines, 100.0).unwrap();
+
+        let violations = thresholds.check(&report);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].target, Target::Directory(FIXTURES.to_string()));
+        assert_eq!(violations[0].to_string(), format!("{}/: lines coverage 95.0% is below 100.0%", FIXTURES));
+        assert!(violations.iter().all(|violation| violation.target != Target::File(FUNC1.to_string())));
+    }
+
+    #[test]
+    fn metric_from_str() {
+        assert_eq!("branches".parse(), Ok(Metric::Branches));
+        assert!("statements".parse::<Metric>().is_err());
+    }
+}
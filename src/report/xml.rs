@@ -0,0 +1,26 @@
+/// Escape the text for an XML attribute or element.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+
+#[cfg(test)]
+mod tests {
+    use report::xml:: { escape };
+
+    #[test]
+    fn escape_special_characters() {
+        assert_eq!(escape("operator<<(std::ostream&, \"x\")"), "operator&lt;&lt;(std::ostream&amp;, &quot;x&quot;)");
+    }
+}
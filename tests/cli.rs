@@ -0,0 +1,21 @@
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
+fn lcov_merge(args: &[&str]) -> Vec<u8> {
+    let output = Command::new(env!("CARGO_BIN_EXE_lcov-merge")).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    output.stdout
+}
+
+#[test]
+fn stdout_and_file_outputs() {
+    let inputs = ["tests/fixtures/fixture1.info", "tests/fixtures/fixture2.info"];
+    let stdout = lcov_merge(&[inputs[0], inputs[1], "-o", "-"]);
+    lcov_merge(&[inputs[0], inputs[1], "-o", "/tmp/cli_output.info"]);
+
+    let mut file = vec![];
+    File::open("/tmp/cli_output.info").unwrap().read_to_end(&mut file).unwrap();
+    assert_eq!(stdout, file);
+    assert!(stdout.ends_with(b"end_of_record\n\n"));
+}
@@ -0,0 +1,12 @@
+#include <stdlib.h>
+
+static void debug(int x) /* LCOV_EXCL_START */
+{
+  abort();
+} /* LCOV_EXCL_STOP */
+
+int main(int argc, char* argv[])
+{
+  if (argc < 0) abort(); /* LCOV_EXCL_LINE */
+  return argc > 1 ? 1 : 0; /* LCOV_EXCL_BR_LINE */
+}